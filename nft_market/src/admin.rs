@@ -0,0 +1,182 @@
+use crate::events::{emit_event, MarketEvent, OwnerChanged, OwnerProposed};
+use crate::{Contract, ContractError, ContractExt};
+use near_sdk::{env, near_bindgen, AccountId};
+
+impl Contract {
+    pub(crate) fn check_not_paused(&self) -> Result<(), ContractError> {
+        if self.paused {
+            return Err(ContractError::Paused);
+        }
+
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn owner(&self) -> &AccountId {
+        &self.owner_id
+    }
+
+    pub fn pending_owner(&self) -> Option<&AccountId> {
+        self.pending_owner_id.as_ref()
+    }
+
+    /// Propose the new owner, who becomes the owner after calling `accept_ownership`.
+    ///
+    /// A previous proposal is replaced, `None` cancels it.
+    #[handle_result]
+    pub fn propose_owner(&mut self, owner_id: Option<AccountId>) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.pending_owner_id = owner_id.clone();
+        emit_event(MarketEvent::OwnerProposed(OwnerProposed {
+            owner_id: self.owner_id.clone(),
+            pending_owner_id: owner_id,
+        }));
+
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn accept_ownership(&mut self) -> Result<(), ContractError> {
+        let account_id = env::predecessor_account_id();
+        if self.pending_owner_id.as_ref() != Some(&account_id) {
+            return Err(ContractError::NotAuthorized(
+                "Only the proposed owner can accept the ownership",
+            ));
+        }
+
+        self.pending_owner_id = None;
+        let prev_owner_id = std::mem::replace(&mut self.owner_id, account_id);
+        emit_event(MarketEvent::OwnerChanged(OwnerChanged {
+            prev_owner_id,
+            owner_id: self.owner_id.clone(),
+        }));
+
+        Ok(())
+    }
+
+    pub fn guardian(&self) -> Option<&AccountId> {
+        self.guardian_id.as_ref()
+    }
+
+    /// Set the guardian, who can pause the market in an emergency, `None` removes the guardian.
+    #[handle_result]
+    pub fn set_guardian(&mut self, guardian_id: Option<AccountId>) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.guardian_id = guardian_id.clone();
+        emit_event(MarketEvent::GuardianChanged { guardian_id });
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stop new orders and trades, cancellations and withdrawals still work.
+    ///
+    /// The market is paused by the owner or the guardian, but only the owner unpauses it.
+    #[handle_result]
+    pub fn pause(&mut self) -> Result<(), ContractError> {
+        let account_id = env::predecessor_account_id();
+        if account_id != self.owner_id && self.guardian_id.as_ref() != Some(&account_id) {
+            return Err(ContractError::NotAuthorized(
+                "Only the market owner or the guardian can pause the market",
+            ));
+        }
+
+        self.paused = true;
+        emit_event(MarketEvent::Paused { account_id });
+
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn unpause(&mut self) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.paused = false;
+        emit_event(MarketEvent::Unpaused {
+            account_id: self.owner_id.clone(),
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn ownership_is_transferred_in_two_steps() {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.propose_owner(Some(accounts(2))).unwrap();
+        assert_eq!(contract.owner(), &accounts(1));
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.accept_ownership(),
+            Err(ContractError::NotAuthorized(_))
+        ));
+        assert!(matches!(
+            contract.propose_owner(Some(accounts(3))),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.accept_ownership().unwrap();
+        assert_eq!(contract.owner(), &accounts(2));
+        assert!(contract.pending_owner().is_none());
+    }
+
+    #[test]
+    fn guardian_pauses_new_orders_and_only_owner_unpauses() {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.set_guardian(Some(accounts(2))).unwrap();
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.pause(),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.pause().unwrap();
+        assert!(contract.is_paused());
+        assert!(matches!(
+            contract.unpause(),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.nft_on_approve(
+                "1".to_string(),
+                accounts(3),
+                1,
+                r#"{"price":"100"}"#.to_string()
+            ),
+            Err(ContractError::Paused)
+        ));
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.unpause().unwrap();
+        assert!(!contract.is_paused());
+    }
+}
@@ -1,37 +1,258 @@
-use battlemon_models::helpers_contract::emit_log_event;
+use crate::events::{emit_event, AskEvent, BidEvent, MarketEvent};
+use crate::storage::OrderId;
+use crate::trade::{check_prepaid_gas, trade_gas};
+use crate::{Auction, ContractAndTokenId, ContractError, DutchAuction, SaleType, StorageKey};
 use battlemon_models::market::ask::AskForContract;
 use battlemon_models::market::bid::BidForContract;
-use battlemon_models::market::events::MarketEventKind;
-use near_sdk::AccountId;
+use near_sdk::json_types::U128;
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, AccountId};
 
 impl crate::Contract {
     /// Add ask for a concrete token.
     ///
     /// The market automatically completes the trade
-    /// if the asker provides a price less than the highest bid.
+    /// if the asker provides a price less than the highest bid in the same currency.
     /// First, the bidder receives the asker's token.
-    /// Then, the asker gets the bidder's Nears held by the market.
-    pub(crate) fn add_ask(&mut self, ask: &AskForContract) {
-        match self.highest_bid_than_ask(ask) {
-            None => {
-                self.asks.insert(ask.token_id().to_owned(), ask.to_owned());
-                emit_log_event(MarketEventKind::AddAsk(ask.to_owned()));
+    /// Then, the asker gets the bidder's Nears or fungible tokens held by the market.
+    ///
+    /// In an English auction the token isn't sold right away,
+    /// the ask's price is the auction's reserve price.
+    /// In a Dutch auction the ask's price is the start price.
+    ///
+    /// A private ask is filled only by bids of `buyer_ids`, an empty list makes the ask public.
+    ///
+    /// When the owner approves the token again, the ask is updated in place with the new approval.
+    /// An auction keeps running with its terms, only a fixed price ask takes the new ones.
+    pub(crate) fn add_ask(
+        &mut self,
+        nft_contract_id: &AccountId,
+        ask: &AskForContract,
+        ft_contract_id: Option<AccountId>,
+        sale_type: SaleType,
+        buyer_ids: Vec<AccountId>,
+    ) -> Result<(), ContractError> {
+        let key = (nft_contract_id.to_owned(), ask.token_id().to_owned());
+        self.remove_expired_bids(&key);
+        if self.refresh_auction_approval(&key, ask) {
+            return Ok(());
+        }
+
+        // The previous ask of the token is replaced together with its storage charge.
+        let prev_currency = self.ask_currency(&key).cloned();
+        if let Some(prev_ask) = self.remove_ask(&key) {
+            // The ask of the previous owner is stale after the token was transferred.
+            if prev_ask.account_id() != ask.account_id() {
+                emit_event(MarketEvent::RemoveAsk(AskEvent::new(
+                    nft_contract_id,
+                    prev_ask,
+                    prev_currency.as_ref(),
+                )));
             }
-            Some(bid) => self.trade(ask.to_owned(), bid, false),
         }
+
+        let bid = match sale_type {
+            SaleType::Auction { .. } => None,
+            _ => self
+                .highest_bid_by(&key, ft_contract_id.as_ref(), |bid| {
+                    buyer_ids.is_empty() || buyer_ids.contains(bid.account_id())
+                })
+                .filter(|bid| bid.price() >= ask.price()),
+        };
+        if let Some(bid) = bid {
+            check_prepaid_gas(trade_gas(ft_contract_id.as_ref()))?;
+            // The bid is removed before the trade, so it can't be cancelled while the trade is in progress.
+            let bid = self.remove_bid(&key, &bid.id).unwrap();
+            emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                nft_contract_id,
+                bid.to_owned(),
+                ft_contract_id.as_ref(),
+            )));
+            self.trade(key.0, ask.to_owned(), bid, false, ft_contract_id);
+            return Ok(());
+        }
+
+        let event = AskEvent::new(nft_contract_id, ask.to_owned(), ft_contract_id.as_ref());
+        self.charge_storage(ask.account_id(), OrderId::Ask(key.clone()), |this| {
+            this.insert_ask(key.clone(), ask.to_owned(), ft_contract_id);
+            this.reserve_ask(key.clone(), buyer_ids);
+            match sale_type {
+                SaleType::Selling => {}
+                SaleType::Auction {
+                    end_at,
+                    min_increment,
+                } => {
+                    this.auctions.insert(
+                        key,
+                        Auction {
+                            end_at,
+                            min_increment,
+                        },
+                    );
+                }
+                SaleType::DutchAuction {
+                    end_at,
+                    floor_price,
+                } => {
+                    let auction = DutchAuction {
+                        start_price: U128(ask.price()),
+                        floor_price,
+                        start_at: env::block_timestamp(),
+                        end_at,
+                    };
+                    this.dutch_auctions.insert(key, auction);
+                }
+            }
+        })?;
+        emit_event(MarketEvent::AddAsk(event));
+
+        Ok(())
+    }
+
+    /// Store the new approval of the owner's auction, returns `false` if there is no such auction.
+    fn refresh_auction_approval(&mut self, key: &ContractAndTokenId, ask: &AskForContract) -> bool {
+        let is_auction = self.auctions.contains_key(key) || self.dutch_auctions.contains_key(key);
+        let prev_ask = match self.asks.get(key) {
+            Some(prev_ask) if is_auction && prev_ask.account_id() == ask.account_id() => prev_ask,
+            _ => return false,
+        };
+
+        // The owner and the token are the same, only the approval is new.
+        let mut ask = ask.to_owned();
+        ask.price = U128(prev_ask.price());
+        self.asks.insert(key.to_owned(), ask.clone());
+        emit_event(MarketEvent::AddAsk(AskEvent::new(
+            &key.0,
+            ask,
+            self.ask_currency(key),
+        )));
+
+        true
+    }
+
+    pub(crate) fn ask_less_than_bid(
+        &self,
+        nft_contract_id: &AccountId,
+        bid: &BidForContract,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Option<AskForContract> {
+        let key = (nft_contract_id.to_owned(), bid.token_id().to_owned());
+        if self.ask_currency(&key) != ft_contract_id
+            || !self.is_buyer_allowed(&key, bid.account_id())
+        {
+            return None;
+        }
+
+        let ask = self.asks.get(&key)?;
+        let price = self.ask_price(&key, ask);
+        if price > bid.price() {
+            return None;
+        }
+
+        // The trade goes for the current price of a Dutch auction.
+        let mut ask = ask.to_owned();
+        ask.price = U128(price);
+        Some(ask)
+    }
+
+    /// Whether the account can fill the ask, anyone can fill a public ask.
+    pub(crate) fn is_buyer_allowed(
+        &self,
+        key: &ContractAndTokenId,
+        account_id: &AccountId,
+    ) -> bool {
+        self.reserved_buyers
+            .get(key)
+            .map_or(true, |buyer_ids| buyer_ids.contains(account_id))
     }
 
-    pub(crate) fn ask_less_than_bid(&self, bid: &BidForContract) -> Option<AskForContract> {
+    pub(crate) fn is_listed_by(&self, key: &ContractAndTokenId, account_id: &AccountId) -> bool {
         self.asks
-            .get(bid.token_id())
-            .filter(|ask| ask.price() <= bid.price())
-            .cloned()
+            .get(key)
+            .map_or(false, |ask| ask.account_id() == account_id)
+    }
+
+    pub(crate) fn is_private_ask(&self, key: &ContractAndTokenId) -> bool {
+        self.reserved_buyers.contains_key(key)
+    }
+
+    /// Reserve the ask for the buyers, an ask without buyers is indexed as public.
+    fn reserve_ask(&mut self, key: ContractAndTokenId, buyer_ids: Vec<AccountId>) {
+        if buyer_ids.is_empty() {
+            self.reserved_buyers.remove(&key);
+            self.public_asks.insert(key);
+        } else {
+            self.public_asks.remove(&key);
+            self.reserved_buyers.insert(key, buyer_ids);
+        }
     }
 
     pub(crate) fn count_asks_for_account(&self, account_id: &AccountId) -> usize {
-        self.asks
-            .iter()
-            .filter(|(_, ask)| ask.account_id() == account_id)
-            .count()
+        self.asks_by_owner
+            .get(account_id)
+            .map_or(0, |keys| keys.len() as usize)
+    }
+
+    /// Store the ask with its currency and index it by the owner.
+    ///
+    /// The previous ask for the same token is replaced.
+    pub(crate) fn insert_ask(
+        &mut self,
+        key: ContractAndTokenId,
+        ask: AskForContract,
+        ft_contract_id: Option<AccountId>,
+    ) {
+        if let Some(prev_ask) = self.asks.insert(key.clone(), ask.clone()) {
+            self.remove_from_owner_index(prev_ask.account_id(), &key);
+        }
+
+        match ft_contract_id {
+            Some(ft_contract_id) => self.ask_currencies.insert(key.clone(), ft_contract_id),
+            None => self.ask_currencies.remove(&key),
+        };
+
+        self.asks_by_owner
+            .entry(ask.account_id().to_owned())
+            .or_insert_with(|| {
+                UnorderedSet::new(StorageKey::AsksByOwnerInner {
+                    account_hash: env::sha256(ask.account_id().as_bytes()),
+                })
+            })
+            .insert(key.clone());
+        self.index_collection_token(&key);
+    }
+
+    pub(crate) fn remove_ask(&mut self, key: &ContractAndTokenId) -> Option<AskForContract> {
+        let ask = self.asks.remove(key)?;
+        self.release_storage(ask.account_id(), &OrderId::Ask(key.to_owned()));
+        self.ask_currencies.remove(key);
+        self.reserved_buyers.remove(key);
+        self.public_asks.remove(key);
+        self.auctions.remove(key);
+        self.dutch_auctions.remove(key);
+        self.remove_from_owner_index(ask.account_id(), key);
+        self.unindex_collection_token(key);
+
+        Some(ask)
+    }
+
+    /// Remove the ask and log its removal with the ask's currency.
+    pub(crate) fn drop_ask(&mut self, key: &ContractAndTokenId) -> Option<AskForContract> {
+        let ft_contract_id = self.ask_currency(key).cloned();
+        let ask = self.remove_ask(key)?;
+        emit_event(MarketEvent::RemoveAsk(AskEvent::new(
+            &key.0,
+            ask.clone(),
+            ft_contract_id.as_ref(),
+        )));
+
+        Some(ask)
+    }
+
+    fn remove_from_owner_index(&mut self, owner_id: &AccountId, key: &ContractAndTokenId) {
+        if let Some(keys) = self.asks_by_owner.get_mut(owner_id) {
+            keys.remove(key);
+            keys.is_empty().then(|| self.asks_by_owner.remove(owner_id));
+        }
     }
 }
@@ -0,0 +1,304 @@
+use crate::consts::AUCTION_EXTENSION;
+use crate::events::{emit_event, AskEvent, BidEvent, MarketEvent};
+use crate::trade::{check_prepaid_gas, trade_gas};
+use crate::{Contract, ContractAndTokenId, ContractError, ContractExt};
+use battlemon_models::market::bid::BidForContract;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+/// How the token is sold, provided in the `nft_approve` message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum SaleType {
+    /// The token is sold for the ask's price.
+    #[default]
+    Selling,
+    /// English auction, the ask's price is the reserve price.
+    Auction { end_at: u64, min_increment: U128 },
+    /// Dutch auction, the price declines from the ask's price to `floor_price` by `end_at`.
+    DutchAuction { end_at: u64, floor_price: U128 },
+}
+
+/// Timed English auction for the token.
+///
+/// The highest bid wins once the auction is settled after `end_at`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Auction {
+    /// Block timestamp in nanoseconds, when the auction ends.
+    pub end_at: u64,
+    /// Minimum amount by which a new bid must exceed the highest one.
+    pub min_increment: U128,
+}
+
+impl Contract {
+    /// Place the bid in the auction for the token.
+    ///
+    /// The bid must be at least the reserve price and exceed the highest bid by the minimum increment.
+    /// The outbid bidder gets the deposit back.
+    /// A bid made shortly before the end extends the auction by `AUCTION_EXTENSION`.
+    /// The bid can't expire before the auction ends, as it could win the token.
+    pub(crate) fn place_auction_bid(
+        &mut self,
+        key: ContractAndTokenId,
+        bid: BidForContract,
+        ft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        let mut auction = self
+            .auctions
+            .get(&key)
+            .cloned()
+            .ok_or(ContractError::NotFound("Auction doesn't exist"))?;
+        let now = env::block_timestamp();
+        if now >= auction.end_at {
+            return Err(ContractError::AuctionError("The auction has already ended"));
+        }
+        if self.ask_currency(&key) != ft_contract_id.as_ref() {
+            return Err(ContractError::BidError(
+                "The bid's currency differs from the auction's one".to_string(),
+            ));
+        }
+        if !self.is_buyer_allowed(&key, bid.account_id()) {
+            return Err(ContractError::BidError(
+                "The auction is reserved for other buyers".to_string(),
+            ));
+        }
+        let end_at = auction.end_at.max(now + AUCTION_EXTENSION);
+        if bid
+            .expire_at()
+            .map_or(false, |expire_at| expire_at < end_at)
+        {
+            return Err(ContractError::BidError(format!(
+                "The bid must not expire before the auction ends at {end_at}"
+            )));
+        }
+
+        let top_bid = self.highest_bid_than_ask(&key, ft_contract_id.as_ref());
+        let min_price = match &top_bid {
+            Some(top_bid) => top_bid.price() + auction.min_increment.0,
+            None => self.asks.get(&key).map_or(0, |ask| ask.price()),
+        };
+        if bid.price() < min_price {
+            return Err(ContractError::BidError(format!(
+                "The bid must be at least {min_price}"
+            )));
+        }
+
+        if let Some(top_bid) = top_bid.and_then(|top_bid| self.remove_bid(&key, &top_bid.id)) {
+            self.refund(
+                top_bid.account_id(),
+                top_bid.price(),
+                ft_contract_id.as_ref(),
+            );
+            emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                &key.0,
+                top_bid,
+                ft_contract_id.as_ref(),
+            )));
+        }
+
+        emit_event(MarketEvent::AddBid(BidEvent::new(
+            &key.0,
+            bid.to_owned(),
+            ft_contract_id.as_ref(),
+        )));
+        self.insert_bid(key.clone(), bid, ft_contract_id)?;
+
+        if end_at > auction.end_at {
+            auction.end_at = end_at;
+            self.auctions.insert(key, auction);
+        }
+
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn auction(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<&Auction> {
+        self.auctions.get(&self.key(nft_contract_id, token_id))
+    }
+
+    /// Complete the auction after its end time, anyone can call it.
+    ///
+    /// The token goes to the highest bidder for the bid's price.
+    /// If nobody has bid the reserve price, the ask is removed.
+    #[handle_result]
+    pub fn settle_auction(
+        &mut self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        self.check_not_paused()?;
+        let key = self.key(nft_contract_id, token_id);
+        let auction = self
+            .auctions
+            .get(&key)
+            .ok_or(ContractError::NotFound("Auction doesn't exist"))?;
+        if env::block_timestamp() < auction.end_at {
+            return Err(ContractError::AuctionError("The auction hasn't ended yet"));
+        }
+
+        let ft_contract_id = self.ask_currency(&key).cloned();
+        let top_bid = self.highest_bid_than_ask(&key, ft_contract_id.as_ref());
+        let ask = self
+            .remove_ask(&key)
+            .ok_or(ContractError::NotFound("Ask doesn't exist"))?;
+
+        match top_bid.filter(|top_bid| top_bid.price() >= ask.price()) {
+            Some(top_bid) => {
+                // The orders are removed before the trade,
+                // so they can't be matched or cancelled while it's in progress.
+                check_prepaid_gas(trade_gas(ft_contract_id.as_ref()))?;
+                let top_bid = self.remove_bid(&key, &top_bid.id).unwrap();
+                emit_event(MarketEvent::RemoveAsk(AskEvent::new(
+                    &key.0,
+                    ask.to_owned(),
+                    ft_contract_id.as_ref(),
+                )));
+                emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                    &key.0,
+                    top_bid.to_owned(),
+                    ft_contract_id.as_ref(),
+                )));
+                self.trade(key.0, ask, top_bid, false, ft_contract_id);
+            }
+            None => emit_event(MarketEvent::RemoveAsk(AskEvent::new(
+                &key.0,
+                ask,
+                ft_contract_id.as_ref(),
+            ))),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::test_utils::DEPOSIT;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, Balance};
+
+    const MIN_INCREMENT: Balance = 1000000000000000000000;
+
+    /// Alice, `accounts(2)`, puts token "1" on auction which ends at 1000,
+    /// bob and charlie, `accounts(3)` and `accounts(4)`, pay for storage.
+    fn start_auction(context: &mut VMContextBuilder) -> Contract {
+        context
+            .current_account_id(accounts(5))
+            .predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context.attached_deposit(DEPOSIT);
+        for account_id in [accounts(2), accounts(3), accounts(4)] {
+            context.predecessor_account_id(account_id);
+            testing_env!(context.build());
+            contract.storage_deposit(None, None).unwrap();
+        }
+
+        let msg = format!(
+            r#"{{"price":"1000","sale_type":{{"auction":{{"end_at":1000,"min_increment":"{MIN_INCREMENT}"}}}}}}"#
+        );
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        contract
+            .nft_on_approve("1".to_string(), accounts(2), 1, msg)
+            .unwrap();
+
+        contract
+    }
+
+    fn bid(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        bidder_id: AccountId,
+        price: Balance,
+    ) -> Result<(), ContractError> {
+        context
+            .predecessor_account_id(bidder_id)
+            .attached_deposit(price);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None)
+    }
+
+    #[test]
+    fn bid_expiring_before_auction_end_fails() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = start_auction(&mut context);
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+
+        assert!(matches!(
+            contract.add_bid("1".to_string(), Some(AUCTION_EXTENSION - 1), None),
+            Err(ContractError::BidError(_))
+        ));
+        contract
+            .add_bid("1".to_string(), Some(AUCTION_EXTENSION), None)
+            .unwrap();
+    }
+
+    #[test]
+    fn outbid_bidder_is_removed_and_bid_below_increment_fails() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = start_auction(&mut context);
+        bid(&mut context, &mut contract, accounts(3), DEPOSIT).unwrap();
+
+        assert!(matches!(
+            bid(&mut context, &mut contract, accounts(4), DEPOSIT + 1),
+            Err(ContractError::BidError(_))
+        ));
+
+        bid(
+            &mut context,
+            &mut contract,
+            accounts(4),
+            DEPOSIT + MIN_INCREMENT,
+        )
+        .unwrap();
+        let bids = contract.bids_for_token("1".to_string(), None, None, None);
+        assert_eq!(bids.len(), 1);
+        assert_eq!(bids[0].account_id(), &accounts(4));
+    }
+
+    #[test]
+    fn late_bid_extends_auction() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = start_auction(&mut context);
+        context.block_timestamp(900);
+        bid(&mut context, &mut contract, accounts(3), DEPOSIT).unwrap();
+
+        let auction = contract.auction("1".to_string(), None).unwrap();
+        assert_eq!(auction.end_at, 900 + AUCTION_EXTENSION);
+    }
+
+    #[test]
+    fn settle_auction_fails_before_end_and_removes_ask_without_bids() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = start_auction(&mut context);
+        assert!(matches!(
+            contract.settle_auction("1".to_string(), None),
+            Err(ContractError::AuctionError(_))
+        ));
+
+        context.block_timestamp(1000);
+        testing_env!(context.build());
+        contract.settle_auction("1".to_string(), None).unwrap();
+        assert!(contract.ask("1".to_string(), None).is_none());
+        assert!(contract.auction("1".to_string(), None).is_none());
+    }
+}
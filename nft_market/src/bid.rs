@@ -1,25 +1,261 @@
+use crate::events::{emit_event, BidEvent, MarketEvent};
+use crate::storage::OrderId;
+use crate::trade::{check_prepaid_gas, trade_gas};
+use crate::{ContractAndTokenId, ContractError, StorageKey};
 use battlemon_models::market::ask::AskForContract;
 use battlemon_models::market::bid::BidForContract;
-use near_sdk::AccountId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, AccountId};
+use std::cmp::Ordering;
+
+/// Result of one call of `prune_expired_bids`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PrunedBids {
+    /// Number of the removed bids.
+    pub removed: u64,
+    /// Index of the first token which the call hasn't walked over.
+    pub next_index: U128,
+}
 
 impl crate::Contract {
     /// Add a bid to the auction to concrete the token.
     ///
-    /// If the bid is more than the asker's token price,
+    /// If the bid is more than the asker's token price in the same currency,
     /// the bidder automatically gets the token.
     /// The market will return the difference between bidder and asker prices to the bidder.
+    pub(crate) fn place_bid(
+        &mut self,
+        nft_contract_id: AccountId,
+        bid: BidForContract,
+        ft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        self.check_nft_contract(&nft_contract_id)?;
+        self.check_not_paused()?;
+        if is_expired(&bid) {
+            return Err(ContractError::BidError(
+                "The bid's expiration time is already in the past".to_string(),
+            ));
+        }
+        self.check_storage_deposits(bid.account_id())?;
+        self.check_orders_per_account(bid.account_id())?;
+        let key = (nft_contract_id, bid.token_id().to_owned());
+        self.remove_expired_bids(&key);
+        self.check_bid_price(&key, &bid, ft_contract_id.as_ref())?;
+        if self.auctions.contains_key(&key) {
+            return self.place_auction_bid(key, bid, ft_contract_id);
+        }
+
+        match self.ask_less_than_bid(&key.0, &bid, ft_contract_id.as_ref()) {
+            None => {
+                self.make_room_for_bid(&key, &bid, ft_contract_id.as_ref())?;
+                emit_event(MarketEvent::AddBid(BidEvent::new(
+                    &key.0,
+                    bid.to_owned(),
+                    ft_contract_id.as_ref(),
+                )));
+                self.insert_bid(key, bid, ft_contract_id)?;
+            }
+            Some(ask) => {
+                check_prepaid_gas(trade_gas(ft_contract_id.as_ref()))?;
+                self.trade(key.0, ask, bid, true, ft_contract_id);
+            }
+        }
 
-    pub(crate) fn highest_bid_than_ask(&self, ask: &AskForContract) -> Option<BidForContract> {
-        let mut bids = self.bids.get(ask.token_id()).cloned().unwrap_or_default();
-        bids.sort_unstable_by_key(|bid| (bid.price(), -(bid.create_at() as i128)));
-        bids.pop()
+        Ok(())
     }
 
-    pub(crate) fn count_bids_for_account(&self, account_id: &AccountId) -> usize {
+    pub(crate) fn highest_bid_than_ask(
+        &self,
+        key: &ContractAndTokenId,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Option<BidForContract> {
+        self.highest_bid_by(key, ft_contract_id, |_| true)
+    }
+
+    /// The highest not expired bid in the currency among the bids which satisfy the predicate.
+    ///
+    /// Bids are stored from the best one, so it's the first matching bid.
+    pub(crate) fn highest_bid_by(
+        &self,
+        key: &ContractAndTokenId,
+        ft_contract_id: Option<&AccountId>,
+        predicate: impl Fn(&BidForContract) -> bool,
+    ) -> Option<BidForContract> {
         self.bids
-            .values()
+            .get(key)?
+            .iter()
+            .filter(|bid| !is_expired(bid))
+            .filter(|bid| self.bid_currency(key, &bid.id) == ft_contract_id)
+            .find(|bid| predicate(bid))
+            .cloned()
+    }
+
+    pub(crate) fn count_bids_for_account(&self, account_id: &AccountId) -> usize {
+        self.bids_by_account.get(account_id).map_or(0, |keys| {
+            keys.iter()
+                .filter_map(|(key, id)| self.find_bid(key, id))
+                .filter(|bid| !is_expired(bid))
+                .count()
+        })
+    }
+
+    pub(crate) fn find_bid(&self, key: &ContractAndTokenId, id: &str) -> Option<&BidForContract> {
+        self.bids.get(key)?.iter().find(|bid| bid.id == id)
+    }
+
+    /// Store the bid with its currency and index it by the bidder.
+    pub(crate) fn insert_bid(
+        &mut self,
+        key: ContractAndTokenId,
+        bid: BidForContract,
+        ft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        let account_id = bid.account_id().to_owned();
+        let order_id = OrderId::Bid(key.clone(), bid.id.clone());
+        self.charge_storage(&account_id, order_id, |this| {
+            if let Some(ft_contract_id) = ft_contract_id {
+                this.bid_currencies
+                    .insert((key.clone(), bid.id.clone()), ft_contract_id);
+            }
+
+            this.bids_by_account
+                .entry(bid.account_id().to_owned())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::BidsByAccountInner {
+                        account_hash: env::sha256(bid.account_id().as_bytes()),
+                    })
+                })
+                .insert((key.clone(), bid.id.clone()));
+            this.index_collection_token(&key);
+
+            let bids = this.bids.entry(key).or_insert_with(Vec::new);
+            let idx = bids.partition_point(|other| cmp_bids(other, &bid) != Ordering::Greater);
+            bids.insert(idx, bid);
+        })
+    }
+
+    pub(crate) fn remove_bid(
+        &mut self,
+        key: &ContractAndTokenId,
+        id: &str,
+    ) -> Option<BidForContract> {
+        let bids = self.bids.get_mut(key)?;
+        let idx = bids.iter().position(|bid| bid.id == id)?;
+        let bid = bids.remove(idx);
+        bids.is_empty().then(|| self.bids.remove(key));
+        self.unindex_collection_token(key);
+        self.release_storage(
+            bid.account_id(),
+            &OrderId::Bid(key.to_owned(), bid.id.clone()),
+        );
+        self.bid_currencies
+            .remove(&(key.to_owned(), bid.id.clone()));
+
+        if let Some(keys) = self.bids_by_account.get_mut(bid.account_id()) {
+            keys.remove(&(key.to_owned(), bid.id.clone()));
+            keys.is_empty()
+                .then(|| self.bids_by_account.remove(bid.account_id()));
+        }
+
+        Some(bid)
+    }
+
+    /// Sell the token to the bidder for the bid's price without storing an ask.
+    ///
+    /// The bid is removed before the trade, so it can't be cancelled while the trade is in progress.
+    pub(crate) fn accept_bid(
+        &mut self,
+        key: ContractAndTokenId,
+        owner_id: AccountId,
+        approval_id: u64,
+        bid_id: &str,
+    ) -> Result<(), ContractError> {
+        if self.auctions.contains_key(&key) {
+            return Err(ContractError::AcceptBidError(
+                "The token is on auction, it must be settled instead",
+            ));
+        }
+
+        let ft_contract_id = self.bid_currency(&key, bid_id).cloned();
+        check_prepaid_gas(trade_gas(ft_contract_id.as_ref()))?;
+        let bid = self
+            .remove_bid(&key, bid_id)
+            .ok_or(ContractError::NotFound(
+                "Bid with provided id doesn't exist",
+            ))?;
+        emit_event(MarketEvent::RemoveBid(BidEvent::new(
+            &key.0,
+            bid.to_owned(),
+            ft_contract_id.as_ref(),
+        )));
+
+        let ask = AskForContract::new(owner_id, key.1.clone(), approval_id, U128(bid.price()));
+        self.trade(key.0, ask, bid, false, ft_contract_id);
+
+        Ok(())
+    }
+
+    /// Remove expired bids for the token and refund their deposits to the bidders.
+    ///
+    /// Returns the number of removed bids.
+    pub(crate) fn remove_expired_bids(&mut self, key: &ContractAndTokenId) -> u64 {
+        let expired_ids: Vec<String> = self
+            .bids
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter(|bid| is_expired(bid))
+            .map(|bid| bid.id.clone())
+            .collect();
+
+        for id in expired_ids.iter() {
+            self.remove_expired_bid(key, id);
+        }
+
+        expired_ids.len() as u64
+    }
+
+    /// Remove the account's expired bids for all tokens and refund their deposits.
+    pub(crate) fn remove_expired_bids_of(&mut self, account_id: &AccountId) {
+        let expired_keys: Vec<(ContractAndTokenId, String)> = self
+            .bids_by_account
+            .get(account_id)
+            .into_iter()
             .flatten()
-            .filter(|bid| bid.account_id() == account_id)
-            .count()
+            .filter(|(key, id)| self.find_bid(key, id).map_or(false, is_expired))
+            .cloned()
+            .collect();
+
+        for (key, id) in expired_keys.iter() {
+            self.remove_expired_bid(key, id);
+        }
+    }
+
+    fn remove_expired_bid(&mut self, key: &ContractAndTokenId, id: &str) {
+        let ft_contract_id = self.bid_currency(key, id).cloned();
+        if let Some(bid) = self.remove_bid(key, id) {
+            self.refund(bid.account_id(), bid.price(), ft_contract_id.as_ref());
+            emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                &key.0,
+                bid,
+                ft_contract_id.as_ref(),
+            )));
+        }
     }
 }
+
+pub(crate) fn is_expired(bid: &BidForContract) -> bool {
+    bid.expire_at()
+        .map_or(false, |expire_at| expire_at <= env::block_timestamp())
+}
+
+/// Order of the token's bids from the best one: the highest price first, then the earliest.
+pub(crate) fn cmp_bids(bid: &BidForContract, other: &BidForContract) -> Ordering {
+    other
+        .price()
+        .cmp(&bid.price())
+        .then_with(|| bid.create_at().cmp(&other.create_at()))
+}
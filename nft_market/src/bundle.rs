@@ -0,0 +1,386 @@
+use crate::consts::{
+    AFTER_ESCROW_GAS, ESCROW_TRANSFER_GAS, MAX_BUNDLE_LEN, NEAR_TRANSFER_GAS, NFT_PAYOUT_GAS,
+    NFT_TOKEN_GAS, PAYMENTS_PER_TRADE, RELEASE_GAS,
+};
+use crate::escrow::{escrow, ApprovedToken};
+use crate::events::{emit_event, MarketEvent};
+use crate::external::nft;
+use crate::helpers::paginate;
+use crate::storage::OrderId;
+use crate::trade::check_prepaid_gas;
+use crate::{Contract, ContractError, ContractExt, StorageKey};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, near_bindgen, AccountId, Balance, Gas, Promise};
+
+/// Several tokens of the same NFT contract sold as one lot at one price.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Bundle {
+    pub id: u64,
+    pub owner_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub items: Vec<BundleItem>,
+    pub price: U128,
+}
+
+/// Token of the bundle and the id of the market's approval for it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BundleItem {
+    pub token_id: TokenId,
+    pub approval_id: u64,
+}
+
+impl Bundle {
+    pub fn token_ids(&self) -> Vec<TokenId> {
+        self.items
+            .iter()
+            .map(|item| item.token_id.clone())
+            .collect()
+    }
+
+    /// The price without the market fee split equally between the tokens,
+    /// the first tokens take the remainder.
+    pub(crate) fn shares(&self, market_fee: Balance) -> Vec<Balance> {
+        let balance = self.price.0 - market_fee;
+        let len = self.items.len() as Balance;
+        (0..len)
+            .map(|i| balance / len + Balance::from(i < balance % len))
+            .collect()
+    }
+}
+
+/// Gas of `on_bundle_payout`: the tokens go to the buyer, then the payments are made.
+pub(crate) fn on_bundle_payout_gas(len: usize) -> Gas {
+    AFTER_ESCROW_GAS + RELEASE_GAS * len as u64 + NEAR_TRANSFER_GAS * PAYMENTS_PER_TRADE
+}
+
+/// Gas of `on_buy_bundle`: the payouts of the tokens with `on_bundle_payout`,
+/// or the return of the tokens to the owner, which takes less.
+pub(crate) fn on_buy_bundle_gas(len: usize) -> Gas {
+    AFTER_ESCROW_GAS + NFT_PAYOUT_GAS * len as u64 + on_bundle_payout_gas(len)
+}
+
+impl Contract {
+    pub(crate) fn count_bundles_for_account(&self, account_id: &AccountId) -> usize {
+        self.bundles_by_owner
+            .get(account_id)
+            .map_or(0, |ids| ids.len() as usize)
+    }
+
+    /// Store the bundle and index it by the owner.
+    pub(crate) fn insert_bundle(&mut self, bundle: Bundle) -> Result<(), ContractError> {
+        let account_id = bundle.owner_id.clone();
+        self.charge_storage(&account_id, OrderId::Bundle(bundle.id), |this| {
+            this.bundles_by_owner
+                .entry(bundle.owner_id.clone())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::BundlesByOwnerInner {
+                        account_hash: env::sha256(bundle.owner_id.as_bytes()),
+                    })
+                })
+                .insert(bundle.id);
+            this.bundles.insert(bundle.id, bundle);
+        })
+    }
+
+    pub(crate) fn remove_bundle(&mut self, id: u64) -> Option<Bundle> {
+        let bundle = self.bundles.remove(&id)?;
+        self.release_storage(&bundle.owner_id, &OrderId::Bundle(id));
+        if let Some(ids) = self.bundles_by_owner.get_mut(&bundle.owner_id) {
+            ids.remove(&id);
+            ids.is_empty()
+                .then(|| self.bundles_by_owner.remove(&bundle.owner_id));
+        }
+
+        Some(bundle)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// List several tokens as one lot, the caller must have approved the market for every token.
+    ///
+    /// The bundle appears in the market after `nft_token` confirms that the caller owns all the tokens.
+    /// Returns the bundle's id.
+    #[handle_result]
+    pub fn add_bundle(
+        &mut self,
+        nft_contract_id: Option<AccountId>,
+        items: Vec<BundleItem>,
+        price: U128,
+    ) -> Result<u64, ContractError> {
+        let nft_contract_id = nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
+        self.check_nft_contract(&nft_contract_id)?;
+        self.check_not_paused()?;
+        if items.len() < 2 || items.len() > MAX_BUNDLE_LEN {
+            return Err(ContractError::BundleError(
+                "A bundle must contain from 2 to 6 tokens",
+            ));
+        }
+        if items
+            .iter()
+            .enumerate()
+            .any(|(i, item)| items[..i].iter().any(|prev| prev.token_id == item.token_id))
+        {
+            return Err(ContractError::BundleError(
+                "A token can't be included in the bundle twice",
+            ));
+        }
+        check_prepaid_gas(NFT_TOKEN_GAS * items.len() as u64 + AFTER_ESCROW_GAS)?;
+        let owner_id = env::predecessor_account_id();
+        self.check_storage_deposits(&owner_id)?;
+        self.check_orders_per_account(&owner_id)?;
+
+        let bundle = Bundle {
+            id: self.next_bundle_id,
+            owner_id,
+            nft_contract_id,
+            items,
+            price,
+        };
+        self.next_bundle_id += 1;
+
+        let tokens = bundle
+            .items
+            .iter()
+            .map(|item| {
+                nft::ext(bundle.nft_contract_id.clone())
+                    .with_static_gas(NFT_TOKEN_GAS)
+                    .nft_token(item.token_id.clone())
+            })
+            .reduce(Promise::and)
+            .unwrap();
+        let id = bundle.id;
+        tokens.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(AFTER_ESCROW_GAS)
+                .on_add_bundle(bundle),
+        );
+
+        Ok(id)
+    }
+
+    #[handle_result]
+    pub fn cancel_bundle(&mut self, bundle_id: u64) -> Result<(), ContractError> {
+        let bundle = self
+            .bundles
+            .get(&bundle_id)
+            .ok_or(ContractError::NotFound("Bundle doesn't exist"))?;
+        if bundle.owner_id != env::predecessor_account_id() {
+            return Err(ContractError::NotAuthorized(
+                "Bundle's owner is not the same as the caller",
+            ));
+        }
+
+        let bundle = self.remove_bundle(bundle_id).unwrap();
+        emit_event(MarketEvent::RemoveBundle(bundle));
+
+        Ok(())
+    }
+
+    /// Buy the whole bundle, the attached deposit must cover the bundle's price.
+    ///
+    /// The tokens are moved to the market first. If any of them can't be moved,
+    /// the moved ones return to the owner and the deposit is refunded,
+    /// otherwise all the tokens go to the buyer.
+    /// The price without the market fee is split equally between the tokens,
+    /// and every share is paid out according to the token's royalties.
+    #[payable]
+    #[handle_result]
+    pub fn buy_bundle(&mut self, bundle_id: u64) -> Result<(), ContractError> {
+        self.check_not_paused()?;
+        let bundle = self
+            .bundles
+            .get(&bundle_id)
+            .ok_or(ContractError::NotFound("Bundle doesn't exist"))?;
+        let deposit = env::attached_deposit();
+        if deposit < bundle.price.0 {
+            return Err(ContractError::BidError(
+                "The attached deposit is less than the bundle's price".to_string(),
+            ));
+        }
+        let buyer_id = env::predecessor_account_id();
+        if buyer_id == bundle.owner_id {
+            return Err(ContractError::BundleError(
+                "The bundle can't be bought by its owner",
+            ));
+        }
+
+        check_prepaid_gas(
+            ESCROW_TRANSFER_GAS * bundle.items.len() as u64 + on_buy_bundle_gas(bundle.items.len()),
+        )?;
+
+        let bundle = self.remove_bundle(bundle_id).unwrap();
+        let tokens: Vec<ApprovedToken> = bundle
+            .items
+            .iter()
+            .map(|item| {
+                (
+                    bundle.nft_contract_id.clone(),
+                    item.token_id.clone(),
+                    item.approval_id,
+                )
+            })
+            .collect();
+        let callback_gas = on_buy_bundle_gas(bundle.items.len());
+        escrow(&tokens).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(callback_gas)
+                .on_buy_bundle(bundle, buyer_id, U128(deposit)),
+        );
+
+        Ok(())
+    }
+
+    pub fn bundle(&self, bundle_id: u64) -> Option<&Bundle> {
+        self.bundles.get(&bundle_id)
+    }
+
+    pub fn bundles(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<&Bundle> {
+        paginate(self.bundles.values(), from_index, limit)
+    }
+
+    pub fn bundles_by_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&Bundle> {
+        let ids = self.bundles_by_owner.get(&account_id).into_iter().flatten();
+        paginate(ids, from_index, limit)
+            .into_iter()
+            .flat_map(|id| self.bundles.get(id))
+            .collect()
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{setup, DEPOSIT};
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    fn items(token_ids: &[&str]) -> Vec<BundleItem> {
+        token_ids
+            .iter()
+            .enumerate()
+            .map(|(approval_id, token_id)| BundleItem {
+                token_id: token_id.to_string(),
+                approval_id: approval_id as u64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn add_bundle_checks_items() {
+        let (mut context, mut contract) = setup(accounts(2), DEPOSIT);
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_bundle(None, items(&["1"]), U128(DEPOSIT)),
+            Err(ContractError::BundleError(_))
+        ));
+        assert!(matches!(
+            contract.add_bundle(
+                None,
+                items(&["1", "2", "3", "4", "5", "6", "7"]),
+                U128(DEPOSIT)
+            ),
+            Err(ContractError::BundleError(_))
+        ));
+        assert!(matches!(
+            contract.add_bundle(None, items(&["1", "2", "1"]), U128(DEPOSIT)),
+            Err(ContractError::BundleError(_))
+        ));
+
+        context.prepaid_gas(Gas(50_000_000_000_000));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_bundle(None, items(&["1", "2"]), U128(DEPOSIT)),
+            Err(ContractError::NotEnoughGas(_))
+        ));
+
+        context.prepaid_gas(Gas(300_000_000_000_000));
+        testing_env!(context.build());
+        assert_eq!(
+            contract
+                .add_bundle(None, items(&["1", "2"]), U128(DEPOSIT))
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn bundle_is_bought_by_other_account_for_its_price() {
+        let (mut context, mut contract) = setup(accounts(2), DEPOSIT);
+        contract
+            .insert_bundle(Bundle {
+                id: 0,
+                owner_id: accounts(2),
+                nft_contract_id: accounts(0),
+                items: items(&["1", "2"]),
+                price: U128(DEPOSIT),
+            })
+            .unwrap();
+        assert_eq!(contract.count_bundles_for_account(&accounts(2)), 1);
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(DEPOSIT - 1);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.buy_bundle(0),
+            Err(ContractError::BidError(_))
+        ));
+        assert!(matches!(
+            contract.cancel_bundle(0),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.buy_bundle(0),
+            Err(ContractError::BundleError(_))
+        ));
+
+        context
+            .predecessor_account_id(accounts(3))
+            .prepaid_gas(Gas(100_000_000_000_000));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.buy_bundle(0),
+            Err(ContractError::NotEnoughGas(_))
+        ));
+
+        context.prepaid_gas(Gas(300_000_000_000_000));
+        testing_env!(context.build());
+        contract.buy_bundle(0).unwrap();
+        assert!(contract.bundle(0).is_none());
+        assert!(contract
+            .bundles_by_owner(accounts(2), None, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn bundle_price_is_split_between_tokens() {
+        let bundle = Bundle {
+            id: 0,
+            owner_id: accounts(2),
+            nft_contract_id: accounts(0),
+            items: items(&["1", "2", "3"]),
+            price: U128(1_002),
+        };
+
+        assert_eq!(bundle.shares(0), vec![334, 334, 334]);
+        assert_eq!(bundle.shares(1), vec![334, 334, 333]);
+    }
+}
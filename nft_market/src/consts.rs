@@ -3,17 +3,64 @@ use battlemon_models::market::bid::BidForContract;
 use near_sdk::env::STORAGE_PRICE_PER_BYTE;
 use near_sdk::{Balance, Gas};
 
+/// Upper estimate of an ordinary order's storage, the minimum storage deposit.
+///
+/// Orders are charged for the exact storage they take, see `Contract::charge_storage`,
+/// so a larger order, like a bundle or a private ask for many buyers, may need more.
 pub const STORAGE_PER_SALE: u128 = minimum_deposit();
 
-
 pub const NO_DEPOSIT: Balance = 0;
 pub const ONE_YOCTO: Balance = 1;
-pub const BUY_METHOD_TOTAL_GAS: Gas = Gas(80_000_000_000_000);
+/// Gas the method which places an order takes itself, before it starts a trade.
+pub const PLACE_ORDER_GAS: Gas = Gas(20_000_000_000_000);
+/// Gas of `nft_transfer_payout`, which computes the payout of up to `MAX_LEN_PAYOUT` receivers.
 pub const NFT_TRANSFER_GAS: Gas = Gas(44_000_000_000_000);
+/// Gas `on_trade` takes itself, without the payments it makes.
 pub const AFTER_NFT_TRANSFER_GAS: Gas = Gas(20_000_000_000_000);
+/// Gas `on_trade` takes to send one payment in Nears.
+pub const NEAR_TRANSFER_GAS: Gas = Gas(2_000_000_000_000);
+/// A payment to every payout receiver, the bidder's change and the market fee.
+pub const PAYMENTS_PER_TRADE: u64 = MAX_LEN_PAYOUT as u64 + 2;
+/// Prepaid gas of a method which may trade the token for Nears.
+pub const BUY_METHOD_TOTAL_GAS: Gas = Gas(PLACE_ORDER_GAS.0
+    + NFT_TRANSFER_GAS.0
+    + AFTER_NFT_TRANSFER_GAS.0
+    + NEAR_TRANSFER_GAS.0 * PAYMENTS_PER_TRADE);
+pub const AFTER_REFUND_GAS: Gas = Gas(5_000_000_000_000);
+pub const FT_TRANSFER_GAS: Gas = Gas(5_000_000_000_000);
+pub const NFT_TOKEN_GAS: Gas = Gas(10_000_000_000_000);
+pub const AFTER_NFT_TOKEN_GAS: Gas = Gas(50_000_000_000_000);
+pub const BASIS_POINTS: u16 = 10_000;
+pub const MAX_MARKET_FEE: u16 = 1_000;
+pub const MAX_LEN_PAYOUT: u32 = 10;
+/// Bids of a token are kept sorted in one vector, so their number is limited by default.
+pub const DEFAULT_MAX_BIDS_PER_TOKEN: u32 = 100;
+pub const MAX_TRADE_HISTORY: u64 = 1_000;
+/// Tokens aggregated by one page of `collection_order_book`.
+pub const MAX_COLLECTION_BOOK_TOKENS: u64 = 100;
+/// Tokens walked by one call of `prune_expired_bids`.
+pub const MAX_PRUNE_TOKENS: u64 = 50;
+/// Bids made this close to the auction's end, in nanoseconds, extend it by the same period.
+pub const AUCTION_EXTENSION: u64 = 10 * 60 * 1_000_000_000;
+/// A full kit: a lemon with its fire arm, cold arm, cloth, cap and back.
+pub const MAX_BUNDLE_LEN: usize = 6;
+pub const ESCROW_TRANSFER_GAS: Gas = Gas(10_000_000_000_000);
+pub const AFTER_ESCROW_GAS: Gas = Gas(20_000_000_000_000);
+pub const AFTER_RELEASE_GAS: Gas = Gas(5_000_000_000_000);
+/// Gas of returning an escrowed token, with the callback which records a failed transfer.
+pub const RELEASE_GAS: Gas = Gas(ESCROW_TRANSFER_GAS.0 + AFTER_RELEASE_GAS.0);
+/// Gas of `nft_payout`, which only computes the payout of the token.
+pub const NFT_PAYOUT_GAS: Gas = Gas(5_000_000_000_000);
+pub const MIGRATE_GAS: Gas = Gas(50_000_000_000_000);
+pub const NFT_SET_USER_GAS: Gas = Gas(10_000_000_000_000);
+pub const AFTER_SET_USER_GAS: Gas = Gas(15_000_000_000_000);
 
 const MAX_ACCOUNT_ID_LENGTH: usize = 64;
 const MAX_TOKEN_ID_LENGTH: usize = 20; //u64::MAX in string representation is 20 chars
+/// Records which an order takes besides itself: the indexes of the order, its token and the charge.
+const RECORDS_PER_ORDER: usize = 14;
+/// Bytes which the storage takes for every record on top of its key and value.
+const RECORD_OVERHEAD: usize = 40;
 
 const fn minimum_deposit() -> Balance {
     let bid_size =
@@ -27,7 +74,10 @@ const fn minimum_deposit() -> Balance {
         ask_size
     };
 
-    STORAGE_PRICE_PER_BYTE * largest as u128
+    let records =
+        RECORDS_PER_ORDER * (RECORD_OVERHEAD + MAX_ACCOUNT_ID_LENGTH + MAX_TOKEN_ID_LENGTH);
+
+    STORAGE_PRICE_PER_BYTE * (largest + records) as u128
 }
 
 #[cfg(test)]
@@ -0,0 +1,43 @@
+use crate::consts::{FT_TRANSFER_GAS, ONE_YOCTO};
+use crate::external::ft;
+use crate::{ContractAndTokenId, ContractError};
+use near_sdk::json_types::U128;
+use near_sdk::{AccountId, Balance, Promise};
+
+impl crate::Contract {
+    pub(crate) fn check_ft_contract(&self, id: &AccountId) -> Result<(), ContractError> {
+        if !self.ft_ids.contains(id) {
+            return Err(ContractError::NotAuthorized(
+                "The fungible token isn't whitelisted by the market",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Fungible token of the ask, `None` if the ask is in Nears.
+    pub(crate) fn ask_currency(&self, key: &ContractAndTokenId) -> Option<&AccountId> {
+        self.ask_currencies.get(key)
+    }
+
+    /// Fungible token of the bid, `None` if the bid is in Nears.
+    pub(crate) fn bid_currency(&self, key: &ContractAndTokenId, id: &str) -> Option<&AccountId> {
+        self.bid_currencies.get(&(key.to_owned(), id.to_owned()))
+    }
+}
+
+/// Transfer `amount` to the receiver in Nears or,
+/// if `ft_contract_id` is provided, in the fungible token.
+pub(crate) fn transfer(
+    ft_contract_id: Option<&AccountId>,
+    receiver_id: &AccountId,
+    amount: Balance,
+) -> Promise {
+    match ft_contract_id {
+        None => Promise::new(receiver_id.to_owned()).transfer(amount),
+        Some(ft_contract_id) => ft::ext(ft_contract_id.to_owned())
+            .with_attached_deposit(ONE_YOCTO)
+            .with_static_gas(FT_TRANSFER_GAS)
+            .ft_transfer(receiver_id.to_owned(), U128(amount), None),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{Contract, ContractAndTokenId, ContractExt};
+use battlemon_models::market::ask::AskForContract;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+
+/// Dutch auction, the ask's price declines linearly
+/// from `start_price` at `start_at` to `floor_price` at `end_at`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DutchAuction {
+    pub start_price: U128,
+    pub floor_price: U128,
+    /// Block timestamp in nanoseconds, when the price starts to decline.
+    pub start_at: u64,
+    /// Block timestamp in nanoseconds, when the price reaches the floor.
+    pub end_at: u64,
+}
+
+impl DutchAuction {
+    pub fn price_at(&self, timestamp: u64) -> Balance {
+        if timestamp <= self.start_at {
+            return self.start_price.0;
+        }
+        if timestamp >= self.end_at {
+            return self.floor_price.0;
+        }
+
+        let elapsed = (timestamp - self.start_at) as u128;
+        let duration = (self.end_at - self.start_at) as u128;
+        let decline = self.start_price.0 - self.floor_price.0;
+
+        self.start_price.0 - decline * elapsed / duration
+    }
+}
+
+impl Contract {
+    /// Price of the ask at the current block timestamp.
+    pub(crate) fn ask_price(&self, key: &ContractAndTokenId, ask: &AskForContract) -> Balance {
+        self.dutch_auctions.get(key).map_or(ask.price(), |auction| {
+            auction.price_at(env::block_timestamp())
+        })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn dutch_auction(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<&DutchAuction> {
+        self.dutch_auctions
+            .get(&self.key(nft_contract_id, token_id))
+    }
+
+    /// Price of the token's ask at the current block timestamp,
+    /// it declines over time for Dutch auctions.
+    pub fn current_price(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<U128> {
+        let key = self.key(nft_contract_id, token_id);
+        let ask = self.asks.get(&key)?;
+
+        Some(U128(self.ask_price(&key, ask)))
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::test_utils::DEPOSIT;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use battlemon_models::market::bid::BidForContract;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn price_declines_linearly_to_floor() {
+        let auction = DutchAuction {
+            start_price: U128(1000),
+            floor_price: U128(200),
+            start_at: 100,
+            end_at: 500,
+        };
+
+        assert_eq!(auction.price_at(0), 1000);
+        assert_eq!(auction.price_at(300), 600);
+        assert_eq!(auction.price_at(500), 200);
+        assert_eq!(auction.price_at(1000), 200);
+    }
+
+    #[test]
+    fn bid_is_matched_with_current_price() {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+
+        let msg = format!(
+            r#"{{"price":"{}","sale_type":{{"dutch_auction":{{"end_at":1000,"floor_price":"{}"}}}}}}"#,
+            DEPOSIT * 3,
+            DEPOSIT
+        );
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        contract
+            .nft_on_approve("1".to_string(), accounts(2), 1, msg)
+            .unwrap();
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(DEPOSIT * 2)
+            .block_timestamp(100);
+        testing_env!(context.build());
+        let bid = BidForContract::new("1".to_string(), None);
+        assert!(contract
+            .ask_less_than_bid(&accounts(0), &bid, None)
+            .is_none());
+
+        context.block_timestamp(500);
+        testing_env!(context.build());
+        let ask = contract
+            .ask_less_than_bid(&accounts(0), &bid, None)
+            .unwrap();
+        assert_eq!(ask.price(), DEPOSIT * 2);
+        assert_eq!(
+            contract.current_price("1".to_string(), None),
+            Some(U128(DEPOSIT * 2))
+        );
+    }
+}
@@ -17,6 +17,34 @@ pub enum ContractError {
     StorageError(&'static str),
     #[error("The item does not exist")]
     NotFound(&'static str),
+    #[error("Invalid market fee: {0}")]
+    FeeError(&'static str),
+    #[error("Auction error: {0}")]
+    AuctionError(&'static str),
+    #[error("Offer error: {0}")]
+    OfferError(&'static str),
+    #[error("Failed to accept bid: {0}")]
+    AcceptBidError(&'static str),
+    #[error("Bundle error: {0}")]
+    BundleError(&'static str),
+    #[error("Swap error: {0}")]
+    SwapError(&'static str),
+    #[error("Rental error: {0}")]
+    RentalError(&'static str),
+    #[error("The market is paused")]
+    Paused,
+    #[error("Not enough gas for the trade, attach at least {0} Tgas")]
+    NotEnoughGas(u64),
+    #[error("The bid's price is less than the minimum of {0}")]
+    BidBelowMinPrice(u128),
+    #[error("The bid must be at least {0} to beat the best bid")]
+    BidBelowIncrement(u128),
+    #[error("The token already has the maximum of {0} bids")]
+    TooManyBids(u32),
+    #[error("The account already has the maximum of {0} open orders")]
+    TooManyOrders(u32),
+    #[error("Failed to migrate the state: {0}")]
+    MigrationError(String),
 }
 
 impl Debug for ContractError {
@@ -0,0 +1,64 @@
+use crate::consts::{AFTER_RELEASE_GAS, ESCROW_TRANSFER_GAS, ONE_YOCTO};
+use crate::external::nft;
+use crate::ContractExt;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{env, AccountId, Promise, PromiseResult};
+
+/// Token approved to the market: NFT contract, token id and approval id.
+pub(crate) type ApprovedToken = (AccountId, TokenId, u64);
+
+/// Move all the tokens to the market with one joint promise.
+///
+/// Orders which exchange several tokens escrow them first,
+/// so the exchange can be unwound if any of the tokens can't be moved.
+pub(crate) fn escrow(tokens: &[ApprovedToken]) -> Promise {
+    tokens
+        .iter()
+        .map(|(nft_contract_id, token_id, approval_id)| {
+            nft::ext(nft_contract_id.to_owned())
+                .with_attached_deposit(ONE_YOCTO)
+                .with_static_gas(ESCROW_TRANSFER_GAS)
+                .nft_transfer(
+                    env::current_account_id(),
+                    token_id.to_owned(),
+                    Some(*approval_id),
+                    None,
+                )
+        })
+        .reduce(Promise::and)
+        .expect("Nothing to escrow")
+}
+
+/// Which of the tokens were moved to the market, read in the callback of `escrow`.
+pub(crate) fn escrowed() -> Vec<bool> {
+    (0..env::promise_results_count())
+        .map(|i| matches!(env::promise_result(i), PromiseResult::Successful(_)))
+        .collect()
+}
+
+impl crate::Contract {
+    /// Transfer the token held by the market to the receiver.
+    ///
+    /// If the transfer fails, the token stays in the market
+    /// and the receiver can take it later with `claim_token`.
+    pub(crate) fn release(
+        &self,
+        nft_contract_id: &AccountId,
+        token_id: &TokenId,
+        receiver_id: &AccountId,
+    ) -> Promise {
+        nft::ext(nft_contract_id.to_owned())
+            .with_attached_deposit(ONE_YOCTO)
+            .with_static_gas(ESCROW_TRANSFER_GAS)
+            .nft_transfer(receiver_id.to_owned(), token_id.to_owned(), None, None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(AFTER_RELEASE_GAS)
+                    .on_release(
+                        nft_contract_id.to_owned(),
+                        token_id.to_owned(),
+                        receiver_id.to_owned(),
+                    ),
+            )
+    }
+}
@@ -0,0 +1,497 @@
+use crate::{Bundle, FeeConfig, Offer, OrderLimits, Rental, Swap};
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, serde_json, AccountId};
+
+/// NEP-297 standard of the market events.
+pub const EVENT_STANDARD: &str = "battlemon_market";
+/// Version of the market events, bumped when the data of an event changes.
+pub const EVENT_VERSION: &str = "1.0.0";
+
+/// NEP-297 envelope of the market events, which serialize as `event` and `data`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog<T> {
+    pub standard: &'static str,
+    pub version: &'static str,
+    #[serde(flatten)]
+    pub event: T,
+}
+
+impl<T: Serialize> EventLog<T> {
+    pub fn new(event: T) -> Self {
+        Self {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event,
+        }
+    }
+}
+
+/// Log the event as `EVENT_JSON:` with the NEP-297 envelope.
+pub(crate) fn emit_event<T: Serialize>(event: T) {
+    let log = serde_json::to_string(&EventLog::new(event))
+        .unwrap_or_else(|_| env::panic_str("Failed to serialize the event"));
+    env::log_str(&format!("EVENT_JSON:{log}"));
+}
+
+/// Events of the market.
+#[derive(Serialize)]
+#[serde(
+    crate = "near_sdk::serde",
+    tag = "event",
+    content = "data",
+    rename_all = "snake_case"
+)]
+pub enum MarketEvent {
+    AddAsk(AskEvent),
+    RemoveAsk(AskEvent),
+    AddBid(BidEvent),
+    RemoveBid(BidEvent),
+    Sale(Sale),
+    TradeFailed(TradeFailed),
+    AddOffer(Offer),
+    RemoveOffer(Offer),
+    AddBundle(Bundle),
+    RemoveBundle(Bundle),
+    BundleSale(BundleSale),
+    BundleTradeFailed(BundleTradeFailed),
+    AddSwap(Swap),
+    RemoveSwap(Swap),
+    SwapCompleted(SwapCompleted),
+    SwapFailed(SwapFailed),
+    AddRental(Rental),
+    RemoveRental(Rental),
+    Rented(Rental),
+    RentFailed(RentFailed),
+    RentalEnded(RentalEnded),
+    Refund(Refund),
+    RefundFailed(Refund),
+    ReleaseFailed(Release),
+    StorageDeposit(StorageChange),
+    StorageWithdraw(StorageChange),
+    StorageUnregister(StorageChange),
+    OwnerProposed(OwnerProposed),
+    OwnerChanged(OwnerChanged),
+    GuardianChanged { guardian_id: Option<AccountId> },
+    Paused { account_id: AccountId },
+    Unpaused { account_id: AccountId },
+    FeeConfigChanged(FeeConfig),
+    OrderLimitsChanged(OrderLimits),
+    NftContractAdded { nft_contract_id: AccountId },
+    NftContractRemoved { nft_contract_id: AccountId },
+    FtContractAdded { ft_contract_id: AccountId },
+    FtContractRemoved { ft_contract_id: AccountId },
+    StateMigrated { from_version: u32, to_version: u32 },
+}
+
+/// The ask for the token of the NFT contract was added or removed.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AskEvent {
+    pub nft_contract_id: AccountId,
+    pub ask: AskForContract,
+    /// Fungible token of the ask, omitted for Nears.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ft_contract_id: Option<AccountId>,
+}
+
+impl AskEvent {
+    pub fn new(
+        nft_contract_id: &AccountId,
+        ask: AskForContract,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Self {
+        Self {
+            nft_contract_id: nft_contract_id.to_owned(),
+            ask,
+            ft_contract_id: ft_contract_id.cloned(),
+        }
+    }
+}
+
+/// The bid for the token of the NFT contract was added or removed.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BidEvent {
+    pub nft_contract_id: AccountId,
+    pub bid: BidForContract,
+    /// Fungible token of the bid, omitted for Nears.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ft_contract_id: Option<AccountId>,
+}
+
+impl BidEvent {
+    pub fn new(
+        nft_contract_id: &AccountId,
+        bid: BidForContract,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Self {
+        Self {
+            nft_contract_id: nft_contract_id.to_owned(),
+            bid,
+            ft_contract_id: ft_contract_id.cloned(),
+        }
+    }
+}
+
+/// The token was sold, the price was split between the market fee, royalties and the seller.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sale {
+    pub nft_contract_id: AccountId,
+    pub prev_owner: AccountId,
+    pub curr_owner: AccountId,
+    pub token_id: TokenId,
+    pub price: U128,
+    pub market_fee: U128,
+    pub royalty: U128,
+    /// Fungible token the trade was paid with, omitted for Nears.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ft_contract_id: Option<AccountId>,
+}
+
+/// The trade couldn't be completed, the bidder's deposit was refunded.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TradeFailed {
+    pub nft_contract_id: AccountId,
+    pub ask: AskForContract,
+    pub bid: BidForContract,
+    pub reason: String,
+}
+
+/// All the tokens of the bundle were sold, royalty holders and the seller got the price without the market fee.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BundleSale {
+    pub bundle_id: u64,
+    pub nft_contract_id: AccountId,
+    pub prev_owner: AccountId,
+    pub curr_owner: AccountId,
+    pub token_ids: Vec<TokenId>,
+    pub price: U128,
+    pub market_fee: U128,
+    pub royalty: U128,
+}
+
+/// The bundle couldn't be sold, its tokens returned to the owner and the buyer's deposit was refunded.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BundleTradeFailed {
+    pub bundle: Bundle,
+    pub buyer_id: AccountId,
+    pub reason: String,
+}
+
+/// The tokens of the swap were exchanged, each side got the other's Nears without the market fee.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapCompleted {
+    pub swap: Swap,
+    pub taker_id: AccountId,
+    pub market_fee: U128,
+}
+
+/// The swap couldn't be completed, the tokens returned to their owners and the Nears were refunded.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapFailed {
+    pub swap: Swap,
+    pub taker_id: AccountId,
+    pub reason: String,
+}
+
+/// The renter couldn't become the token's user, the deposit was refunded.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RentFailed {
+    pub rental: Rental,
+    pub renter_id: AccountId,
+    pub reason: String,
+}
+
+/// The lease expired, the owner got the rent without the market fee.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RentalEnded {
+    pub rental: Rental,
+    pub market_fee: U128,
+}
+
+/// Escrowed Nears or fungible tokens were returned to the account.
+///
+/// A failed refund is credited to the claimable balance of the account.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Refund {
+    pub account_id: AccountId,
+    pub amount: U128,
+    /// Fungible token of the refund, omitted for Nears.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ft_contract_id: Option<AccountId>,
+}
+
+/// Escrowed token which the market failed to transfer to the receiver.
+///
+/// The token stays in the market until the receiver claims it with `claim_token`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Release {
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub receiver_id: AccountId,
+}
+
+/// The storage deposit of the account changed by `amount`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageChange {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// The owner proposed the new owner, `None` cancels the proposal.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnerProposed {
+    pub owner_id: AccountId,
+    pub pending_owner_id: Option<AccountId>,
+}
+
+/// The proposed owner accepted the ownership.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnerChanged {
+    pub prev_owner_id: AccountId,
+    pub owner_id: AccountId,
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::consts::STORAGE_PER_SALE;
+    use crate::Contract;
+    use near_sdk::serde_json::{json, Value};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseError};
+
+    fn setup(predecessor_account_id: AccountId, attached_deposit: u128) -> Contract {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(predecessor_account_id)
+            .attached_deposit(attached_deposit);
+        testing_env!(context.build());
+        Contract::init(accounts(5))
+    }
+
+    /// Parse the logs, which must all be market events.
+    fn logged_events() -> Vec<Value> {
+        get_logs()
+            .iter()
+            .map(|log| {
+                let json = log.strip_prefix("EVENT_JSON:").expect("Not an event log");
+                near_sdk::serde_json::from_str(json).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn event_is_logged_in_nep297_envelope() {
+        setup(accounts(1), 0);
+        emit_event(MarketEvent::Paused {
+            account_id: accounts(1),
+        });
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"battlemon_market","version":"1.0.0","event":"paused","data":{"account_id":"bob"}}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn bid_event_is_logged_in_nep297_envelope() {
+        setup(accounts(1), 100);
+        let bid = BidForContract::new("1".to_string(), None);
+        let expected_json = json!({
+            "standard": "battlemon_market",
+            "version": "1.0.0",
+            "event": "remove_bid",
+            "data": {
+                "nft_contract_id": accounts(2),
+                "bid": bid,
+                "ft_contract_id": accounts(3),
+            },
+        });
+
+        emit_event(MarketEvent::RemoveBid(BidEvent::new(
+            &accounts(2),
+            bid,
+            Some(&accounts(3)),
+        )));
+
+        assert_eq!(logged_events(), vec![expected_json]);
+    }
+
+    #[test]
+    fn fee_config_change_is_logged() {
+        let mut contract = setup(accounts(1), 0);
+        contract.set_fee_config(250, accounts(2)).unwrap();
+
+        assert_eq!(
+            logged_events(),
+            vec![json!({
+                "standard": "battlemon_market",
+                "version": "1.0.0",
+                "event": "fee_config_changed",
+                "data": {
+                    "fee": 250,
+                    "treasury_id": "charlie",
+                }
+            })]
+        );
+    }
+
+    #[test]
+    fn order_limits_change_is_logged() {
+        let mut contract = setup(accounts(1), 0);
+        contract
+            .set_order_limits(OrderLimits {
+                min_bid_price: Some(U128(100)),
+                max_bids_per_token: Some(10),
+                max_orders_per_account: None,
+                min_bid_increment: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            logged_events(),
+            vec![json!({
+                "standard": "battlemon_market",
+                "version": "1.0.0",
+                "event": "order_limits_changed",
+                "data": {
+                    "min_bid_price": "100",
+                    "max_bids_per_token": 10,
+                    "max_orders_per_account": null,
+                    "min_bid_increment": null,
+                }
+            })]
+        );
+    }
+
+    #[test]
+    fn refund_is_logged() {
+        let contract = setup(accounts(1), 0);
+        contract.refund(&accounts(2), 100, None);
+        contract.refund(&accounts(2), 200, Some(&accounts(3)));
+
+        assert_eq!(
+            logged_events(),
+            vec![
+                json!({
+                    "standard": "battlemon_market",
+                    "version": "1.0.0",
+                    "event": "refund",
+                    "data": {
+                        "account_id": "charlie",
+                        "amount": "100",
+                    }
+                }),
+                json!({
+                    "standard": "battlemon_market",
+                    "version": "1.0.0",
+                    "event": "refund",
+                    "data": {
+                        "account_id": "charlie",
+                        "amount": "200",
+                        "ft_contract_id": "danny",
+                    }
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_refund_is_logged() {
+        let mut contract = setup(accounts(0), 0);
+        contract.on_refund(accounts(2), U128(100), None, Ok(()));
+        contract.on_refund(accounts(2), U128(100), None, Err(PromiseError::Failed));
+
+        assert_eq!(
+            logged_events(),
+            vec![json!({
+                "standard": "battlemon_market",
+                "version": "1.0.0",
+                "event": "refund_failed",
+                "data": {
+                    "account_id": "charlie",
+                    "amount": "100",
+                }
+            })]
+        );
+    }
+
+    #[test]
+    fn storage_withdraw_and_unregister_are_logged() {
+        let mut contract = setup(accounts(1), STORAGE_PER_SALE);
+        contract.storage_deposit(None, None).unwrap();
+
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1);
+        testing_env!(context.build());
+        contract.storage_withdraw(Some(U128(10))).unwrap();
+        contract.storage_unregister(None).unwrap();
+
+        assert_eq!(
+            logged_events(),
+            vec![
+                json!({
+                    "standard": "battlemon_market",
+                    "version": "1.0.0",
+                    "event": "storage_withdraw",
+                    "data": {
+                        "account_id": "bob",
+                        "amount": "10",
+                    }
+                }),
+                json!({
+                    "standard": "battlemon_market",
+                    "version": "1.0.0",
+                    "event": "storage_unregister",
+                    "data": {
+                        "account_id": "bob",
+                        "amount": (STORAGE_PER_SALE - 10).to_string(),
+                    }
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn storage_deposit_is_logged() {
+        let mut contract = setup(accounts(1), STORAGE_PER_SALE);
+        contract.storage_deposit(Some(accounts(2)), None).unwrap();
+
+        assert_eq!(
+            logged_events(),
+            vec![json!({
+                "standard": "battlemon_market",
+                "version": "1.0.0",
+                "event": "storage_deposit",
+                "data": {
+                    "account_id": "charlie",
+                    "amount": STORAGE_PER_SALE.to_string(),
+                }
+            })]
+        );
+    }
+}
@@ -1,5 +1,15 @@
 use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{AccountId, Promise};
+use std::collections::HashMap;
+
+/// Accounts and amounts to pay them out after the token sale, according to NEP-199.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
 
 #[near_sdk::ext_contract(nft)]
 pub trait Nft {
@@ -11,20 +21,30 @@ pub trait Nft {
         memo: Option<String>,
     ) -> Promise;
 
+    fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: Option<u32>) -> Payout;
+
+    fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout;
+
     fn nft_token(&self, token_id: TokenId) -> Promise;
+
+    fn nft_set_user(
+        &mut self,
+        token_id: TokenId,
+        user_id: AccountId,
+        expires_at: u64,
+        approval_id: Option<u64>,
+    );
+}
+
+#[near_sdk::ext_contract(ft)]
+pub trait Ft {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
 }
-//
-// #[near_sdk::ext_contract]
-// trait ExtSelf {
-//     fn after_nft_transfer_for_ask(&mut self, sale: AskForContract, buyer_id: AccountId) -> Promise;
-//     fn after_nft_transfer_for_bid(&mut self, sale: BidForContract, owner_id: AccountId) -> PromiseOrValue<()>;
-//
-//     fn after_nft_token(
-//         &mut self,
-//         bidder_id: AccountId,
-//         token_id: TokenId,
-//         #[rustfmt::skip]
-//         #[callback_result]
-//         result: Result<Option<token_metadata_ext::TokenExt>, PromiseError>,
-//     ) -> PromiseOrValue<()>;
-// }
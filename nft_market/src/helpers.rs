@@ -1,20 +1,28 @@
-use crate::{ContractError, STORAGE_PER_SALE};
-use battlemon_models::helpers_contract::emit_log_event;
+use crate::events::{emit_event, BidEvent, MarketEvent};
+use crate::{ContractAndTokenId, ContractError, STORAGE_PER_SALE};
+use battlemon_models::market::ask::AskForContract;
 use battlemon_models::market::bid::BidForContract;
-use battlemon_models::market::events::MarketEventKind;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
 use near_sdk::{env, AccountId};
 
 impl crate::Contract {
     pub(crate) fn total_orders_by_id(&self, id: &AccountId) -> usize {
-        self.count_bids_for_account(id) + self.count_asks_for_account(id)
+        self.count_bids_for_account(id)
+            + self.count_asks_for_account(id)
+            + self.count_offers_for_account(id)
+            + self.count_bundles_for_account(id)
+            + self.count_swaps_for_account(id)
+            + self.count_rentals_for_account(id)
     }
 
     pub(crate) fn check_storage_deposits(&self, id: &AccountId) -> Result<(), ContractError> {
         let paid_storage = self.storage_deposits.get(id).copied().unwrap_or_default();
+        let available_storage = paid_storage.saturating_sub(self.storage_used_by(id));
 
-        let required_storage = (self.total_orders_by_id(id) + 1) as u128 * STORAGE_PER_SALE;
-
-        if paid_storage < required_storage {
+        // An early check, orders are charged for the exact storage after they are stored
+        // and `charge_storage` fails if the deposit doesn't cover a larger order.
+        if available_storage < STORAGE_PER_SALE {
             return Err(ContractError::StorageError(
                 "Not enough storage deposits to create new order",
             ));
@@ -23,31 +31,73 @@ impl crate::Contract {
         Ok(())
     }
 
-    pub(crate) fn clean_ask_and_bid(&mut self, bid: &BidForContract) {
-        let token_id = bid.token_id();
-        if let Some(ask) = self.asks.remove(token_id) {
-            emit_log_event(MarketEventKind::RemoveAsk(ask))
+    pub(crate) fn check_owner(&self) -> Result<(), ContractError> {
+        if env::predecessor_account_id() != self.owner_id {
+            return Err(ContractError::NotAuthorized(
+                "Only the market owner can call this method",
+            ));
         }
 
-        if let Some(bids) = self.bids.get_mut(token_id) {
-            emit_log_and_remove_bid(&bid.id, bids);
-            bids.is_empty().then(|| self.bids.remove(token_id));
+        Ok(())
+    }
+
+    pub(crate) fn check_nft_contract(&self, id: &AccountId) -> Result<(), ContractError> {
+        if !self.nft_ids.contains(id) {
+            return Err(ContractError::NotAuthorized(
+                "The NFT contract isn't whitelisted by the market",
+            ));
         }
+
+        Ok(())
     }
-}
 
-pub(crate) fn emit_log_and_remove_bid(id: &str, bids: &mut Vec<BidForContract>) {
-    if let Some(idx) = bids.iter().position(|b| b.id == id) {
-        let bid = bids.swap_remove(idx);
-        emit_log_event(MarketEventKind::RemoveBid(bid));
+    /// Build the order key, `nft_contract_id` defaults to the market's default NFT contract.
+    pub(crate) fn key(
+        &self,
+        nft_contract_id: Option<AccountId>,
+        token_id: TokenId,
+    ) -> ContractAndTokenId {
+        (
+            nft_contract_id.unwrap_or_else(|| self.nft_id.clone()),
+            token_id,
+        )
+    }
+
+    /// Remove the ask of the failed trade, unless the token was listed again in the meantime.
+    ///
+    /// Offers and accepted bids are traded without a stored ask, so only
+    /// an ask of the same owner with the same approval belongs to the trade.
+    pub(crate) fn remove_failed_ask(&mut self, nft_contract_id: &AccountId, ask: &AskForContract) {
+        let key = (nft_contract_id.to_owned(), ask.token_id().to_owned());
+        let is_traded = self.asks.get(&key).map_or(false, |stored| {
+            stored.account_id() == ask.account_id() && stored.approval_id() == ask.approval_id()
+        });
+        if is_traded {
+            self.drop_ask(&key);
+        }
     }
-}
 
-pub fn check_cross_contract_call(id: &AccountId) -> Result<(), ContractError> {
-    if env::predecessor_account_id() != *id {
-        return Err(ContractError::NotAuthorized(
-            "The predecessor account id isn't allowed",
-        ));
+    pub(crate) fn clean_ask_and_bid(&mut self, nft_contract_id: &AccountId, bid: &BidForContract) {
+        let key = (nft_contract_id.to_owned(), bid.token_id().to_owned());
+        self.drop_ask(&key);
+
+        let ft_contract_id = self.bid_currency(&key, &bid.id).cloned();
+        if let Some(bid) = self.remove_bid(&key, &bid.id) {
+            emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                nft_contract_id,
+                bid,
+                ft_contract_id.as_ref(),
+            )));
+        }
     }
-    Ok(())
+}
+
+pub(crate) fn paginate<I: Iterator>(
+    iter: I,
+    from_index: Option<U128>,
+    limit: Option<u64>,
+) -> Vec<I::Item> {
+    iter.skip(from_index.map_or(0, |index| index.0 as usize))
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect()
 }
@@ -0,0 +1,197 @@
+use crate::helpers::paginate;
+use crate::{ContractAndTokenId, StorageKey};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::store::LookupMap;
+use near_sdk::AccountId;
+
+/// Completed trade stored in the market's history.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TradeRecord {
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub prev_owner: AccountId,
+    pub curr_owner: AccountId,
+    pub price: U128,
+    /// Fungible token the trade was paid with, omitted for Nears.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ft_contract_id: Option<AccountId>,
+    pub date: u64,
+}
+
+/// Bounded trade history indexed by token and by participant.
+///
+/// When the history is full, the oldest record is evicted to make room for the new one.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct TradeHistory {
+    records: LookupMap<u64, TradeRecord>,
+    by_token: LookupMap<ContractAndTokenId, Vec<u64>>,
+    by_account: LookupMap<AccountId, Vec<u64>>,
+    first_id: u64,
+    next_id: u64,
+    max_len: u64,
+}
+
+impl TradeHistory {
+    pub fn new(max_len: u64) -> Self {
+        Self {
+            records: LookupMap::new(StorageKey::TradeHistory),
+            by_token: LookupMap::new(StorageKey::TradeHistoryByToken),
+            by_account: LookupMap::new(StorageKey::TradeHistoryByAccount),
+            first_id: 0,
+            next_id: 0,
+            max_len,
+        }
+    }
+
+    /// Write the cached records to the storage, so `env::storage_usage` accounts for them.
+    pub fn flush(&mut self) {
+        self.records.flush();
+        self.by_token.flush();
+        self.by_account.flush();
+    }
+
+    pub fn len(&self) -> u64 {
+        self.next_id - self.first_id
+    }
+
+    pub fn add(&mut self, record: TradeRecord) {
+        if self.len() >= self.max_len {
+            self.evict_oldest();
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let key = (record.nft_contract_id.clone(), record.token_id.clone());
+        self.by_token.entry(key).or_default().push(id);
+        self.by_account
+            .entry(record.prev_owner.clone())
+            .or_default()
+            .push(id);
+        if record.curr_owner != record.prev_owner {
+            self.by_account
+                .entry(record.curr_owner.clone())
+                .or_default()
+                .push(id);
+        }
+
+        self.records.insert(id, record);
+    }
+
+    pub fn records(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<&TradeRecord> {
+        let ids = paginate(self.first_id..self.next_id, from_index, limit);
+        ids.iter().flat_map(|id| self.records.get(id)).collect()
+    }
+
+    pub fn records_by_token(
+        &self,
+        key: &ContractAndTokenId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&TradeRecord> {
+        let ids = self.by_token.get(key).into_iter().flatten();
+        paginate(ids, from_index, limit)
+            .into_iter()
+            .flat_map(|id| self.records.get(id))
+            .collect()
+    }
+
+    pub fn records_by_account(
+        &self,
+        account_id: &AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&TradeRecord> {
+        let ids = self.by_account.get(account_id).into_iter().flatten();
+        paginate(ids, from_index, limit)
+            .into_iter()
+            .flat_map(|id| self.records.get(id))
+            .collect()
+    }
+
+    fn evict_oldest(&mut self) {
+        let id = self.first_id;
+        self.first_id += 1;
+
+        let record = match self.records.remove(&id) {
+            Some(record) => record,
+            None => return,
+        };
+
+        let key = (record.nft_contract_id, record.token_id);
+        if let Some(ids) = self.by_token.get_mut(&key) {
+            ids.retain(|i| *i != id);
+            if ids.is_empty() {
+                self.by_token.remove(&key);
+            }
+        }
+
+        for account_id in [record.prev_owner, record.curr_owner] {
+            if let Some(ids) = self.by_account.get_mut(&account_id) {
+                ids.retain(|i| *i != id);
+                if ids.is_empty() {
+                    self.by_account.remove(&account_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn record(token_id: &str, prev_owner: AccountId, curr_owner: AccountId) -> TradeRecord {
+        TradeRecord {
+            nft_contract_id: accounts(0),
+            token_id: token_id.to_string(),
+            prev_owner,
+            curr_owner,
+            price: U128(1),
+            ft_contract_id: None,
+            date: 0,
+        }
+    }
+
+    #[test]
+    fn records_are_indexed_by_token_and_account() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut history = TradeHistory::new(10);
+        history.add(record("1", accounts(1), accounts(2)));
+        history.add(record("2", accounts(2), accounts(3)));
+
+        let key = (accounts(0), "1".to_string());
+        assert_eq!(history.records_by_token(&key, None, None).len(), 1);
+        assert_eq!(
+            history.records_by_account(&accounts(2), None, None).len(),
+            2
+        );
+        assert_eq!(history.records(Some(U128(1)), None)[0].token_id, "2");
+    }
+
+    #[test]
+    fn oldest_record_is_evicted_when_history_is_full() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut history = TradeHistory::new(2);
+        history.add(record("1", accounts(1), accounts(2)));
+        history.add(record("2", accounts(2), accounts(3)));
+        history.add(record("3", accounts(3), accounts(4)));
+
+        assert_eq!(history.len(), 2);
+        let key = (accounts(0), "1".to_string());
+        assert!(history.records_by_token(&key, None, None).is_empty());
+        assert!(history
+            .records_by_account(&accounts(1), None, None)
+            .is_empty());
+        assert_eq!(
+            history.records_by_account(&accounts(2), None, None).len(),
+            1
+        );
+    }
+}
@@ -1,33 +1,104 @@
 pub use ask::*;
-use battlemon_models::helpers_contract::emit_log_event;
-use battlemon_models::market::events::MarketEventKind;
+pub use auction::{Auction, SaleType};
 use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
 pub use bid::*;
+pub use bundle::{Bundle, BundleItem};
 use consts::*;
+pub use dutch_auction::DutchAuction;
 use error::*;
+use events::{emit_event, BidEvent, MarketEvent};
+use history::TradeHistory;
+pub use history::TradeRecord;
+pub use limits::OrderLimits;
 use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::U128;
-use near_sdk::store::{LookupMap, UnorderedMap};
+use near_sdk::store::{LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::{env, near_bindgen, AccountId, Balance, BorshStorageKey, PanicOnDefault, Promise};
-use std::ops::AddAssign;
-
+pub use offer::{Offer, TraitFilter};
+pub use order_book::{OrderBook, PriceLevel};
+pub use payout::FeeConfig;
+pub use rental::{Lease, Rental};
+use storage::OrderId;
+pub use storage::StorageUsage;
+pub use swap::Swap;
+
+mod admin;
 mod ask;
+mod auction;
 mod bid;
+mod bundle;
 mod consts;
+mod currency;
+mod dutch_auction;
 mod error;
+mod escrow;
+mod events;
 mod external;
 mod helpers;
+mod history;
+mod limits;
+mod offer;
+mod order_book;
+mod payout;
+mod refund;
+mod rental;
+mod storage;
+mod swap;
+#[cfg(test)]
+mod test_utils;
 mod trade;
+mod upgrade;
+mod views;
 mod xcc;
 
+/// Key of an order: the NFT contract id and the token id within that contract.
+pub type ContractAndTokenId = (AccountId, TokenId);
+
 #[near_bindgen]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct Contract {
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    guardian_id: Option<AccountId>,
+    paused: bool,
     nft_id: AccountId,
-    asks: UnorderedMap<TokenId, AskForContract>,
-    bids: UnorderedMap<TokenId, Vec<BidForContract>>,
+    nft_ids: UnorderedSet<AccountId>,
+    ft_ids: UnorderedSet<AccountId>,
+    fee_config: FeeConfig,
+    order_limits: OrderLimits,
+    asks: UnorderedMap<ContractAndTokenId, AskForContract>,
+    /// Keys of the asks without reserved buyers, which are listed by `asks`.
+    public_asks: UnorderedSet<ContractAndTokenId>,
+    bids: UnorderedMap<ContractAndTokenId, Vec<BidForContract>>,
+    ask_currencies: LookupMap<ContractAndTokenId, AccountId>,
+    reserved_buyers: LookupMap<ContractAndTokenId, Vec<AccountId>>,
+    bid_currencies: LookupMap<(ContractAndTokenId, String), AccountId>,
+    auctions: LookupMap<ContractAndTokenId, Auction>,
+    dutch_auctions: LookupMap<ContractAndTokenId, DutchAuction>,
+    offers: UnorderedMap<u64, Offer>,
+    offers_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_offer_id: u64,
+    bundles: UnorderedMap<u64, Bundle>,
+    bundles_by_owner: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_bundle_id: u64,
+    swaps: UnorderedMap<u64, Swap>,
+    swaps_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_swap_id: u64,
+    rentals: UnorderedMap<ContractAndTokenId, Rental>,
+    rentals_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    asks_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    bids_by_account: LookupMap<AccountId, UnorderedSet<(ContractAndTokenId, String)>>,
+    /// Tokens with an ask or bids by their NFT contracts, which `collection_order_book` pages through.
+    tokens_by_collection: LookupMap<AccountId, UnorderedSet<TokenId>>,
     storage_deposits: LookupMap<AccountId, Balance>,
+    storage_used: LookupMap<AccountId, u64>,
+    order_storage: LookupMap<OrderId, u64>,
+    claimable_balances: LookupMap<AccountId, Balance>,
+    claimable_ft_balances: LookupMap<(AccountId, AccountId), Balance>,
+    /// Escrowed tokens which failed to be transferred, with the accounts which can claim them.
+    claimable_tokens: LookupMap<ContractAndTokenId, AccountId>,
+    trade_history: TradeHistory,
 }
 
 #[derive(BorshSerialize, BorshStorageKey)]
@@ -35,56 +106,217 @@ enum StorageKey {
     Asks,
     Bids,
     StorageDeposits,
+    ClaimableBalances,
+    NftIds,
+    AsksByOwner,
+    AsksByOwnerInner { account_hash: Vec<u8> },
+    BidsByAccount,
+    BidsByAccountInner { account_hash: Vec<u8> },
+    TradeHistory,
+    TradeHistoryByToken,
+    TradeHistoryByAccount,
+    FtIds,
+    AskCurrencies,
+    BidCurrencies,
+    ClaimableFtBalances,
+    Auctions,
+    DutchAuctions,
+    Offers,
+    OffersByAccount,
+    OffersByAccountInner { account_hash: Vec<u8> },
+    Bundles,
+    BundlesByOwner,
+    BundlesByOwnerInner { account_hash: Vec<u8> },
+    ReservedBuyers,
+    Swaps,
+    SwapsByAccount,
+    SwapsByAccountInner { account_hash: Vec<u8> },
+    StorageUsed,
+    OrderStorage,
+    Rentals,
+    RentalsByOwner,
+    RentalsByOwnerInner { account_hash: Vec<u8> },
+    PublicAsks,
+    ClaimableTokens,
+    TokensByCollection,
+    TokensByCollectionInner { account_hash: Vec<u8> },
 }
 
 #[near_bindgen]
 impl Contract {
+    /// Initialize the market with `nft_id` as the default NFT contract.
+    ///
+    /// The caller becomes the market owner, who manages the whitelist of NFT contracts.
     #[init]
     pub fn init(nft_id: AccountId) -> Self {
+        let owner_id = env::predecessor_account_id();
+        let mut nft_ids = UnorderedSet::new(StorageKey::NftIds);
+        nft_ids.insert(nft_id.clone());
+        upgrade::write_state_version();
+
         Self {
+            fee_config: FeeConfig {
+                fee: 0,
+                treasury_id: owner_id.clone(),
+            },
+            order_limits: OrderLimits::default(),
+            owner_id,
+            pending_owner_id: None,
+            guardian_id: None,
+            paused: false,
             nft_id,
+            nft_ids,
+            ft_ids: UnorderedSet::new(StorageKey::FtIds),
             asks: UnorderedMap::new(StorageKey::Asks),
+            public_asks: UnorderedSet::new(StorageKey::PublicAsks),
             bids: UnorderedMap::new(StorageKey::Bids),
+            ask_currencies: LookupMap::new(StorageKey::AskCurrencies),
+            reserved_buyers: LookupMap::new(StorageKey::ReservedBuyers),
+            bid_currencies: LookupMap::new(StorageKey::BidCurrencies),
+            auctions: LookupMap::new(StorageKey::Auctions),
+            dutch_auctions: LookupMap::new(StorageKey::DutchAuctions),
+            offers: UnorderedMap::new(StorageKey::Offers),
+            offers_by_account: LookupMap::new(StorageKey::OffersByAccount),
+            next_offer_id: 0,
+            bundles: UnorderedMap::new(StorageKey::Bundles),
+            bundles_by_owner: LookupMap::new(StorageKey::BundlesByOwner),
+            next_bundle_id: 0,
+            swaps: UnorderedMap::new(StorageKey::Swaps),
+            swaps_by_account: LookupMap::new(StorageKey::SwapsByAccount),
+            next_swap_id: 0,
+            rentals: UnorderedMap::new(StorageKey::Rentals),
+            rentals_by_owner: LookupMap::new(StorageKey::RentalsByOwner),
+            asks_by_owner: LookupMap::new(StorageKey::AsksByOwner),
+            bids_by_account: LookupMap::new(StorageKey::BidsByAccount),
+            tokens_by_collection: LookupMap::new(StorageKey::TokensByCollection),
             storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+            storage_used: LookupMap::new(StorageKey::StorageUsed),
+            order_storage: LookupMap::new(StorageKey::OrderStorage),
+            claimable_balances: LookupMap::new(StorageKey::ClaimableBalances),
+            claimable_ft_balances: LookupMap::new(StorageKey::ClaimableFtBalances),
+            claimable_tokens: LookupMap::new(StorageKey::ClaimableTokens),
+            trade_history: TradeHistory::new(MAX_TRADE_HISTORY),
+        }
+    }
+
+    #[handle_result]
+    pub fn add_nft_contract(&mut self, nft_contract_id: AccountId) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.nft_ids.insert(nft_contract_id.clone());
+        emit_event(MarketEvent::NftContractAdded { nft_contract_id });
+
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn remove_nft_contract(&mut self, nft_contract_id: AccountId) -> Result<(), ContractError> {
+        self.check_owner()?;
+        if nft_contract_id == self.nft_id {
+            return Err(ContractError::NotAuthorized(
+                "The default NFT contract can't be removed",
+            ));
         }
+        self.nft_ids.remove(&nft_contract_id);
+        emit_event(MarketEvent::NftContractRemoved { nft_contract_id });
+
+        Ok(())
+    }
+
+    pub fn nft_contracts(&self) -> Vec<&AccountId> {
+        self.nft_ids.iter().collect()
+    }
+
+    /// Allow orders to be paid with the fungible token, e.g. Juice.
+    #[handle_result]
+    pub fn add_ft_contract(&mut self, ft_contract_id: AccountId) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.ft_ids.insert(ft_contract_id.clone());
+        emit_event(MarketEvent::FtContractAdded { ft_contract_id });
+
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn remove_ft_contract(&mut self, ft_contract_id: AccountId) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.ft_ids.remove(&ft_contract_id);
+        emit_event(MarketEvent::FtContractRemoved { ft_contract_id });
+
+        Ok(())
+    }
+
+    pub fn ft_contracts(&self) -> Vec<&AccountId> {
+        self.ft_ids.iter().collect()
+    }
+
+    /// Set the market fee in basis points, which is paid to `treasury_id` from every trade.
+    #[handle_result]
+    pub fn set_fee_config(
+        &mut self,
+        fee: u16,
+        treasury_id: AccountId,
+    ) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.fee_config = FeeConfig::new(fee, treasury_id)?;
+        emit_event(MarketEvent::FeeConfigChanged(self.fee_config.clone()));
+
+        Ok(())
     }
 
+    pub fn fee_config(&self) -> &FeeConfig {
+        &self.fee_config
+    }
+
+    /// Add a bid for the token with the attached deposit as the price.
+    ///
+    /// `expire_at` is a block timestamp in nanoseconds, after which the bid
+    /// is no longer matched and can be pruned with its deposit refunded.
+    /// `nft_contract_id` defaults to the contract the market was initialized with.
+    /// Bids in fungible tokens are placed with `ft_transfer_call` to the market.
     #[payable]
     #[handle_result]
     pub fn add_bid(
         &mut self,
         token_id: TokenId,
         expire_at: Option<u64>,
+        nft_contract_id: Option<AccountId>,
     ) -> Result<(), ContractError> {
-        self.check_storage_deposits(&env::predecessor_account_id())?;
-
+        let nft_contract_id = nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
         let bid = BidForContract::new(token_id, expire_at);
-        match self.ask_less_than_bid(&bid) {
-            None => {
-                emit_log_event(MarketEventKind::AddBid(bid.to_owned()));
-
-                self.bids
-                    .entry(bid.token_id().to_owned())
-                    .and_modify(|bids| {
-                        bids.push(bid.clone());
-                    })
-                    .or_insert_with(|| vec![bid]);
-            }
-            Some(ask) => self.trade(ask, bid, true),
-        }
 
-        Ok(())
+        self.place_bid(nft_contract_id, bid, None)
+    }
+
+    pub fn ask(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<&AskForContract> {
+        self.asks.get(&self.key(nft_contract_id, token_id))
     }
 
-    pub fn ask(&self, token_id: TokenId) -> Option<&AskForContract> {
-        self.asks.get(&token_id)
+    /// Accounts which the private ask is reserved for, `None` for a public ask.
+    ///
+    /// Private asks are hidden from `asks` and `asks_by_owner`, but still available by `ask`.
+    pub fn reserved_buyers(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<&Vec<AccountId>> {
+        self.reserved_buyers
+            .get(&self.key(nft_contract_id, token_id))
     }
 
     #[handle_result]
-    pub fn cancel_ask(&mut self, token_id: TokenId) -> Result<(), ContractError> {
+    pub fn cancel_ask(
+        &mut self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        let key = self.key(nft_contract_id, token_id);
         let ask = self
             .asks
-            .get(&token_id)
+            .get(&key)
             .ok_or(ContractError::NotFound("Ask doesn't exist"))?
             .to_owned();
 
@@ -93,93 +325,179 @@ impl Contract {
                 "Ask's owner is not the same as the caller",
             ));
         }
+        if self.auctions.contains_key(&key)
+            && self
+                .highest_bid_than_ask(&key, self.ask_currency(&key))
+                .is_some()
+        {
+            return Err(ContractError::AuctionError(
+                "The auction with bids can't be cancelled",
+            ));
+        }
 
-        self.asks.remove(&token_id);
-        emit_log_event(MarketEventKind::RemoveAsk(ask));
+        self.drop_ask(&key);
 
         Ok(())
     }
 
-    pub fn bids(&self, token_id: TokenId) -> Option<&Vec<BidForContract>> {
-        self.bids.get(&token_id)
+    pub fn bids(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<&Vec<BidForContract>> {
+        self.bids.get(&self.key(nft_contract_id, token_id))
+    }
+
+    /// Remove expired bids and refund their deposits to the bidders.
+    ///
+    /// Walks over at most `limit` tokens with bids, starting from `from_index`.
+    /// The limit defaults to and is capped by `MAX_PRUNE_TOKENS`.
+    /// A token without bids left drops out of the list, so the next call
+    /// continues from the returned `next_index` rather than `from_index + limit`.
+    pub fn prune_expired_bids(
+        &mut self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> PrunedBids {
+        let from_index = from_index.map_or(0, |index| index.0);
+        let limit = limit.map_or(MAX_PRUNE_TOKENS, |limit| limit.min(MAX_PRUNE_TOKENS));
+        let keys: Vec<ContractAndTokenId> = helpers::paginate(
+            self.bids.keys().cloned(),
+            Some(U128(from_index)),
+            Some(limit),
+        );
+
+        let mut pruned = PrunedBids {
+            removed: 0,
+            next_index: U128(from_index),
+        };
+        for key in keys.iter() {
+            pruned.removed += self.remove_expired_bids(key);
+            if self.bids.contains_key(key) {
+                pruned.next_index.0 += 1;
+            }
+        }
+
+        pruned
     }
 
     #[handle_result]
-    pub fn cancel_bid(&mut self, token_id: TokenId, bid_id: String) -> Result<(), ContractError> {
-        let bids = self.bids.get_mut(&token_id).ok_or(ContractError::NotFound(
+    pub fn cancel_bid(
+        &mut self,
+        token_id: TokenId,
+        bid_id: String,
+        nft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        let key = self.key(nft_contract_id, token_id);
+        let bids = self.bids.get(&key).ok_or(ContractError::NotFound(
             "Bids for provided token id don't exist",
         ))?;
 
-        let idx = bids
+        let bid = bids
             .iter()
-            .position(|b| b.id == bid_id)
+            .find(|b| b.id == bid_id)
             .ok_or(ContractError::NotFound(
                 "Bid with provided id doesn't exist",
             ))?;
-        let bid = bids.get(idx).unwrap();
         if bid.account_id() != &env::predecessor_account_id() {
             return Err(ContractError::NotAuthorized(
                 "Ask's owner is not the same as the caller",
             ));
         }
+        if self.auctions.contains_key(&key)
+            && matches!(
+                self.highest_bid_than_ask(&key, self.ask_currency(&key)),
+                Some(top_bid) if top_bid.id == bid_id
+            )
+        {
+            return Err(ContractError::AuctionError(
+                "The highest bid can't be cancelled during the auction",
+            ));
+        }
 
-        let bid = bids.swap_remove(idx);
-        emit_log_event(MarketEventKind::RemoveBid(bid));
-
-        bids.is_empty().then(|| self.bids.remove(&token_id));
+        let ft_contract_id = self.bid_currency(&key, &bid_id).cloned();
+        let bid = self.remove_bid(&key, &bid_id).unwrap();
+        self.refund(bid.account_id(), bid.price(), ft_contract_id.as_ref());
+        emit_event(MarketEvent::RemoveBid(BidEvent::new(
+            &key.0,
+            bid,
+            ft_contract_id.as_ref(),
+        )));
 
         Ok(())
     }
 
-    #[payable]
-    #[handle_result]
-    pub fn storage_deposit(&mut self, account_id: Option<AccountId>) -> Result<(), ContractError> {
-        let storage_account_id = account_id.unwrap_or_else(env::predecessor_account_id);
-        let deposit = env::attached_deposit();
-        check_deposit(deposit)?;
-
-        self.storage_deposits
-            .entry(storage_account_id)
-            .or_default()
-            .add_assign(deposit);
-
-        Ok(())
+    /// Balance which the market failed to return to the account.
+    ///
+    /// `ft_contract_id` selects the fungible token balance instead of Nears.
+    pub fn claimable_balance_of(
+        &self,
+        account_id: AccountId,
+        ft_contract_id: Option<AccountId>,
+    ) -> U128 {
+        let ret = match ft_contract_id {
+            None => self.claimable_balances.get(&account_id),
+            Some(ft_contract_id) => self
+                .claimable_ft_balances
+                .get(&(account_id, ft_contract_id)),
+        };
+
+        U128(ret.copied().unwrap_or_default())
     }
 
     #[payable]
     #[handle_result]
-    pub fn storage_withdraw(&mut self) -> Result<U128, ContractError> {
+    pub fn claim_balance(
+        &mut self,
+        ft_contract_id: Option<AccountId>,
+    ) -> Result<U128, ContractError> {
         check_one_yocto()?;
-        let owner_id = env::predecessor_account_id();
-        let deposit = self.storage_deposits.remove(&owner_id).unwrap_or_default();
-
-        let amount_orders = self.total_orders_by_id(&owner_id);
-        let effective_deposit = (amount_orders as u128) * STORAGE_PER_SALE;
-        let diff = deposit - effective_deposit;
-
-        if diff > 0 {
-            Promise::new(owner_id.clone()).transfer(diff);
+        let account_id = env::predecessor_account_id();
+        let amount = match &ft_contract_id {
+            None => self.claimable_balances.remove(&account_id),
+            Some(ft_contract_id) => self
+                .claimable_ft_balances
+                .remove(&(account_id.clone(), ft_contract_id.clone())),
         }
+        .ok_or(ContractError::NotFound("Claimable balance doesn't exist"))?;
 
-        if effective_deposit > 0 {
-            self.storage_deposits.insert(owner_id, effective_deposit);
-        }
+        self.refund(&account_id, amount, ft_contract_id.as_ref());
 
-        Ok(U128(diff))
+        Ok(U128(amount))
     }
 
-    pub fn storage_minimum_balance(&self) -> U128 {
-        U128(STORAGE_PER_SALE)
+    /// Account which can claim the token held by the market after a failed transfer.
+    pub fn claimable_token(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Option<&AccountId> {
+        self.claimable_tokens
+            .get(&self.key(nft_contract_id, token_id))
     }
 
-    pub fn storage_balance_of(&self, account_id: AccountId) -> U128 {
-        let ret = self
-            .storage_deposits
-            .get(&account_id)
-            .copied()
-            .unwrap_or_default();
+    /// Transfer the token which the market failed to return to the caller.
+    #[payable]
+    #[handle_result]
+    pub fn claim_token(
+        &mut self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Result<Promise, ContractError> {
+        check_one_yocto()?;
+        let key = self.key(nft_contract_id, token_id);
+        let receiver_id = self
+            .claimable_tokens
+            .get(&key)
+            .ok_or(ContractError::NotFound("Claimable token doesn't exist"))?;
+        if receiver_id != &env::predecessor_account_id() {
+            return Err(ContractError::NotAuthorized(
+                "The token is claimable by another account",
+            ));
+        }
 
-        U128(ret)
+        let receiver_id = self.claimable_tokens.remove(&key).unwrap();
+        Ok(self.release(&key.0, &key.1, &receiver_id))
     }
 }
 
@@ -191,37 +509,66 @@ fn check_one_yocto() -> Result<(), ContractError> {
     Ok(())
 }
 
-fn check_deposit(deposit: Balance) -> Result<(), ContractError> {
-    if deposit < STORAGE_PER_SALE {
-        return Err(ContractError::InsufficientDeposit);
-    }
-
-    Ok(())
-}
-
 #[cfg(all(not(target_arch = "wasm32"), test))]
 mod tests {
     use super::*;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use crate::test_utils::{get_context, DEPOSIT};
+    use crate::xcc::{FungibleTokenReceiver, NonFungibleTokenApprovalReceiver};
+    use near_sdk::test_utils::accounts;
     use near_sdk::testing_env;
 
-    const DEPOSIT: Balance = 1960000000000000000000;
-
-    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
-        let mut builder = VMContextBuilder::new();
-        builder
-            .current_account_id(accounts(0))
-            .signer_account_id(predecessor_account_id.clone())
-            .predecessor_account_id(predecessor_account_id);
-        builder
-    }
-
     #[test]
     fn contract_new_works() {
         let context = get_context(accounts(1));
         testing_env!(context.build());
         let contract = Contract::init(accounts(1));
         assert_eq!(contract.nft_id, accounts(1));
+        assert_eq!(contract.nft_contracts(), vec![&accounts(1)]);
+    }
+
+    #[test]
+    fn add_nft_contract_works_only_for_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.add_nft_contract(accounts(2)).unwrap();
+        assert_eq!(contract.nft_contracts().len(), 2);
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_nft_contract(accounts(4)),
+            Err(ContractError::NotAuthorized(_))
+        ));
+    }
+
+    #[test]
+    fn set_fee_config_fails_if_fee_too_high() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.set_fee_config(250, accounts(2)).unwrap();
+        assert_eq!(contract.fee_config().fee, 250);
+        assert_eq!(contract.fee_config().calculate(10_000), 250);
+
+        assert!(matches!(
+            contract.set_fee_config(MAX_MARKET_FEE + 1, accounts(2)),
+            Err(ContractError::FeeError(_))
+        ));
+    }
+
+    #[test]
+    fn add_bid_fails_for_not_whitelisted_nft_contract() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+
+        assert!(matches!(
+            contract.add_bid("1".to_string(), None, Some(accounts(2))),
+            Err(ContractError::NotAuthorized(_))
+        ));
     }
 
     #[test]
@@ -238,7 +585,7 @@ mod tests {
         context.attached_deposit(DEPOSIT);
         testing_env!(context.build());
         let mut contract = Contract::init(accounts(0));
-        contract.storage_deposit(None).unwrap();
+        contract.storage_deposit(None, None).unwrap();
 
         assert_eq!(contract.storage_deposits.get(&accounts(1)), Some(&DEPOSIT));
     }
@@ -249,8 +596,8 @@ mod tests {
         context.attached_deposit(DEPOSIT);
         testing_env!(context.build());
         let mut contract = Contract::init(accounts(0));
-        contract.storage_deposit(None).unwrap();
-        contract.storage_deposit(None).unwrap();
+        contract.storage_deposit(None, None).unwrap();
+        contract.storage_deposit(None, None).unwrap();
 
         assert_eq!(
             contract.storage_deposits.get(&accounts(1)),
@@ -264,313 +611,366 @@ mod tests {
         context.attached_deposit(DEPOSIT);
         testing_env!(context.build());
         let mut contract = Contract::init(accounts(0));
-        contract.storage_deposit(None).unwrap();
+        contract.storage_deposit(None, None).unwrap();
         context.attached_deposit(DEPOSIT - 1);
         testing_env!(context.build());
 
-        matches!(
-            contract.storage_deposit(None),
+        assert!(matches!(
+            contract.storage_deposit(Some(accounts(2)), None),
             Err(ContractError::InsufficientDeposit)
+        ));
+        contract.storage_deposit(None, None).unwrap();
+    }
+
+    #[test]
+    fn storage_withdraw_keeps_deposit_of_orders() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT * 3);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        let balance = contract.storage_deposit(None, Some(true)).unwrap();
+        assert_eq!(balance.total, U128(DEPOSIT));
+
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        let balance = contract.storage_balance_of(accounts(1)).unwrap();
+        let used = contract.storage_usage_of(accounts(1)).unwrap().used.0;
+        assert!(used > 0 && used < DEPOSIT);
+        assert_eq!(balance.total, U128(DEPOSIT * 2));
+        assert_eq!(balance.available, U128(DEPOSIT * 2 - used));
+
+        context.attached_deposit(1);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.storage_withdraw(Some(U128(DEPOSIT * 2 - used + 1))),
+            Err(ContractError::StorageError(_))
+        ));
+        assert!(matches!(
+            contract.storage_unregister(None),
+            Err(ContractError::StorageError(_))
+        ));
+        let balance = contract.storage_withdraw(None).unwrap();
+        assert_eq!(balance.total, U128(used));
+        assert_eq!(balance.available, U128(0));
+    }
+
+    #[test]
+    fn order_over_storage_deposit_fails() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+
+        // The reserved buyers take more storage than the minimum deposit covers.
+        let buyer_ids: Vec<String> = (0..30).map(|i| format!("{i:0>59}.near")).collect();
+        let msg = near_sdk::serde_json::json!({ "price": "100", "buyer_ids": buyer_ids });
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.nft_on_approve("1".to_string(), accounts(1), 1, msg.to_string()),
+            Err(ContractError::StorageError(_))
+        ));
+    }
+
+    #[test]
+    fn forced_unregister_cancels_orders() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT * 2);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        contract
+            .nft_on_approve(
+                "2".to_string(),
+                accounts(1),
+                1,
+                r#"{"price":"100"}"#.to_string(),
+            )
+            .unwrap();
+        assert_eq!(contract.total_orders_by_id(&accounts(1)), 2);
+
+        context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.storage_unregister(None),
+            Err(ContractError::StorageError(_))
+        ));
+        assert!(contract.storage_unregister(Some(true)).unwrap());
+        assert_eq!(contract.total_orders_by_id(&accounts(1)), 0);
+        assert!(contract.ask("2".to_string(), None).is_none());
+        assert!(contract
+            .bids_for_token("1".to_string(), None, None, None)
+            .is_empty());
+        assert!(contract.storage_balance_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn unregister_removes_expired_bids() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT * 2).block_timestamp(100);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        contract.add_bid("1".to_string(), Some(200), None).unwrap();
+
+        context.attached_deposit(1).block_timestamp(200);
+        testing_env!(context.build());
+        assert_eq!(contract.total_orders_by_id(&accounts(1)), 0);
+        assert!(contract.storage_unregister(None).unwrap());
+        assert!(contract.bids("1".to_string(), None).is_none());
+        assert_eq!(contract.total_bids_by_account(accounts(1)), 0);
+        assert!(contract.storage_usage_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn bid_matched_by_new_ask_can_not_be_cancelled_during_trade() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        let bid_id = contract.bids_for_token("1".to_string(), None, None, None)[0]
+            .id
+            .clone();
+        contract.storage_deposit(Some(accounts(2)), None).unwrap();
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract
+            .nft_on_approve(
+                "1".to_string(),
+                accounts(2),
+                1,
+                format!("{{\"price\":\"{DEPOSIT}\"}}"),
+            )
+            .unwrap();
+        assert!(contract.ask("1".to_string(), None).is_none());
+        assert!(contract
+            .bids_for_token("1".to_string(), None, None, None)
+            .is_empty());
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.cancel_bid("1".to_string(), bid_id, None),
+            Err(ContractError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn storage_charge_is_released_when_order_is_removed() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        let bid_id = contract.bids_for_token("1".to_string(), None, None, None)[0]
+            .id
+            .clone();
+        assert!(contract.storage_usage_of(accounts(1)).unwrap().used.0 > 0);
+
+        contract.cancel_bid("1".to_string(), bid_id, None).unwrap();
+        let usage = contract.storage_usage_of(accounts(1)).unwrap();
+        assert_eq!(usage.used, U128(0));
+        assert_eq!(usage.available, U128(DEPOSIT));
+    }
+
+    #[test]
+    fn bids_are_indexed_by_account() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        contract.storage_deposit(None, None).unwrap();
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        contract.add_bid("2".to_string(), None, None).unwrap();
+
+        assert_eq!(contract.total_bids_by_account(accounts(1)), 2);
+        assert_eq!(
+            contract.bids_by_account(accounts(1), None, Some(1)).len(),
+            1
+        );
+        assert_eq!(contract.count_bids_for_account(&accounts(1)), 2);
+
+        let bid_id = contract.bids_for_token("1".to_string(), None, None, None)[0]
+            .id
+            .clone();
+        contract.cancel_bid("1".to_string(), bid_id, None).unwrap();
+        assert_eq!(contract.total_bids_by_account(accounts(1)), 1);
+        assert!(contract.bids_by_account(accounts(2), None, None).is_empty());
+    }
+
+    #[test]
+    fn add_bid_fails_if_expire_at_in_the_past() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT).block_timestamp(100);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+
+        assert!(matches!(
+            contract.add_bid("1".to_string(), Some(100), None),
+            Err(ContractError::BidError(_))
+        ));
+    }
+
+    #[test]
+    fn expired_bid_is_not_counted_and_pruned() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT).block_timestamp(100);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        contract.add_bid("1".to_string(), Some(200), None).unwrap();
+        assert_eq!(contract.count_bids_for_account(&accounts(1)), 1);
+
+        context.block_timestamp(200);
+        testing_env!(context.build());
+        assert_eq!(contract.count_bids_for_account(&accounts(1)), 0);
+        assert_eq!(
+            contract.prune_expired_bids(None, None),
+            PrunedBids {
+                removed: 1,
+                next_index: U128(0),
+            }
+        );
+        assert!(contract.bids("1".to_string(), None).is_none());
+    }
+
+    #[test]
+    fn prune_expired_bids_continues_after_kept_tokens() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT * 3).block_timestamp(100);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), Some(200), None).unwrap();
+        contract.add_bid("2".to_string(), None, None).unwrap();
+        contract.add_bid("3".to_string(), Some(200), None).unwrap();
+
+        context.block_timestamp(200);
+        testing_env!(context.build());
+        let pruned = contract.prune_expired_bids(None, Some(2));
+        assert_eq!(pruned.removed, 1);
+        assert_eq!(pruned.next_index, U128(1));
+
+        let pruned = contract.prune_expired_bids(Some(pruned.next_index), Some(2));
+        assert_eq!(pruned.removed, 1);
+        assert!(contract.bids("2".to_string(), None).is_some());
+        assert!(contract.bids("3".to_string(), None).is_none());
+    }
+
+    #[test]
+    fn ft_on_transfer_fails_for_not_whitelisted_ft_contract() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), r#"{"token_id":"1"}"#.to_string()),
+            Err(ContractError::NotAuthorized(_))
+        ));
+    }
+
+    #[test]
+    fn bid_in_fungible_token_is_not_matched_with_ask_in_nears() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.add_ft_contract(accounts(3)).unwrap();
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+        contract.storage_deposit(Some(accounts(4)), None).unwrap();
+
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        contract
+            .nft_on_approve(
+                "1".to_string(),
+                accounts(2),
+                1,
+                r#"{"price":"100"}"#.to_string(),
+            )
+            .unwrap();
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        contract
+            .ft_on_transfer(accounts(4), U128(200), r#"{"token_id":"1"}"#.to_string())
+            .unwrap();
+
+        let key = (accounts(0), "1".to_string());
+        assert!(contract.ask("1".to_string(), None).is_some());
+        assert_eq!(contract.total_bids_for_token("1".to_string(), None), 1);
+        let bid = &contract.bids_for_token("1".to_string(), None, None, None)[0];
+        assert_eq!(bid.account_id(), &accounts(4));
+        assert_eq!(contract.bid_currency(&key, &bid.id), Some(&accounts(3)));
+    }
+
+    #[test]
+    fn private_ask_is_hidden_and_not_matched_with_other_bidders() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        let msg = format!(r#"{{"price":"100","buyer_ids":["{}"]}}"#, accounts(3));
+        contract
+            .nft_on_approve("1".to_string(), accounts(2), 1, msg)
+            .unwrap();
+        assert!(contract.ask("1".to_string(), None).is_some());
+        assert_eq!(
+            contract.reserved_buyers("1".to_string(), None),
+            Some(&vec![accounts(3)])
         );
+        assert!(contract.asks(None, None).is_empty());
+        assert_eq!(contract.total_asks(), 0);
+        assert!(contract.asks_by_owner(accounts(2), None, None).is_empty());
+
+        context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(200);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        assert!(contract.ask("1".to_string(), None).is_some());
+        assert_eq!(contract.total_bids_for_token("1".to_string(), None), 1);
     }
 }
-// pub fn list_asks(&self) -> Vec<AskForContract> {
-//     self.asks.iter().map(|(_, v)| v).collect()
-// }
-//
-// #[payable]
-// pub fn buy(&mut self, token_id: TokenId) -> Promise {
-//     self.process_purchase(token_id, OrderType::AcceptAsk)
-// }
-//
-// #[payable]
-// pub fn bid(&mut self, token_id: TokenId) -> Promise {
-//     let deposit = env::attached_deposit();
-//     require!(deposit > 0, "attached deposit must be more than 0.");
-//
-//     let bidder_id = env::predecessor_account_id();
-//
-//     ext_nft::nft_token(
-//         token_id.clone(),
-//         self.nft_id.clone(),
-//         NO_DEPOSIT,
-//         Gas(20_000_000_000_000),
-//     )
-//     .then(ext_self::after_nft_token(
-//         bidder_id,
-//         token_id,
-//         env::current_account_id(),
-//         deposit,
-//         Gas(90_000_000_000_000),
-//     ))
-// }
-//
-// pub fn list_bids(&self) -> Vec<(TokenId, Vec<BidForContract>)> {
-//     self.bids.to_vec()
-// }
-//
-// fn process_purchase(&mut self, token_id: TokenId, order_type: OrderType) -> Promise {
-//     let approval_id;
-//     let callback;
-//     let new_owner_id;
-//
-//     match order_type {
-//         OrderType::AcceptAsk => {
-//             new_owner_id = env::predecessor_account_id();
-//
-//             let sale = self.get_ask(&token_id);
-//
-//             // todo: to refactor from here ->
-//             let deposit = env::attached_deposit();
-//             let panic_msg = format!(
-//                 "attached deposit less than token's price.\n\
-//                  Attached deposit is {}, token's price is {}",
-//                 deposit, sale.price.0
-//             );
-//             require!(deposit >= sale.price.0, panic_msg);
-//
-//             require!(
-//                 env::prepaid_gas() >= BUY_METHOD_TOTAL_GAS,
-//                 format!("attached gas less than: {:?}", BUY_METHOD_TOTAL_GAS)
-//             );
-//             // <- until here
-//
-//             approval_id = sale.approval_id;
-//
-//             callback = ext_self::after_nft_transfer_for_ask(
-//                 sale,
-//                 new_owner_id.clone(),
-//                 env::current_account_id(),
-//                 deposit,
-//                 AFTER_NFT_TRANSFER_GAS,
-//             );
-//         }
-//         OrderType::AskLessBid(bidder_id) => {
-//             let sale = self.get_ask(&token_id);
-//
-//             // todo: to refactor from here ->
-//             let deposit = env::attached_deposit();
-//             let panic_msg = format!(
-//                 "attached deposit less than token's price.\n\
-//                  Attached deposit is {}, token's price is {}",
-//                 deposit, sale.price.0
-//             );
-//             require!(deposit >= sale.price.0, panic_msg);
-//
-//             require!(
-//                 env::prepaid_gas() >= BUY_METHOD_TOTAL_GAS,
-//                 format!("attached gas less than: {:?}", BUY_METHOD_TOTAL_GAS)
-//             );
-//             // <- until here
-//             new_owner_id = bidder_id.clone();
-//             approval_id = sale.approval_id;
-//
-//             callback = ext_self::after_nft_transfer_for_ask(
-//                 sale,
-//                 bidder_id,
-//                 env::current_account_id(),
-//                 deposit,
-//                 AFTER_NFT_TRANSFER_GAS,
-//             );
-//         }
-//         OrderType::AcceptBid {
-//             owner_id,
-//             approval_id: _approval_id,
-//         } => {
-//             approval_id = _approval_id;
-//
-//             let mut ordered_bids = self.bids.get(&token_id).unwrap_or_else(|| {
-//                 panic_str(format!("bids for token is: {}, doesn't exists", token_id).as_str())
-//             });
-//
-//             ordered_bids.sort_by_key(|v| v.price.0);
-//
-//             let last = ordered_bids
-//                 .pop()
-//                 .unwrap_or_else(|| panic_str("bids is empty"));
-//
-//             self.bids.insert(&token_id, &ordered_bids);
-//
-//             new_owner_id = last.bidder_id.clone();
-//             callback = ext_self::after_nft_transfer_for_bid(
-//                 last,
-//                 owner_id,
-//                 env::current_account_id(),
-//                 env::attached_deposit(),
-//                 AFTER_NFT_TRANSFER_GAS,
-//             )
-//         }
-//     };
-//
-//     ext_nft::nft_transfer(
-//         new_owner_id,
-//         token_id,
-//         Some(approval_id),
-//         None,
-//         self.nft_id.clone(),
-//         ONE_YOCTO,
-//         NFT_TRANSFER_GAS,
-//     )
-//     .then(callback)
-// }
-//
-// fn get_ask(&self, token_id: &TokenId) -> AskForContract {
-//     self.asks.get(token_id).unwrap_or_else(|| {
-//         panic_str(format!("token with id {} doesn't sell", token_id).as_str())
-//     })
-// }
-//
-// #[private]
-// #[payable]
-// pub fn after_nft_transfer_for_ask(&mut self, sale: AskForContract, buyer_id: AccountId) -> Promise {
-//     let deposit = env::attached_deposit();
-//     match env::promise_result(0) {
-//         PromiseResult::Successful(_) => {
-//             self.asks.remove(&sale.token_id);
-//             let log = json!({
-//                 "prev_owner": sale.owner_id,
-//                 "curr_owner": buyer_id,
-//                 "token_id": sale.token_id,
-//                 "price": sale.price,
-//             });
-//             log!(format!("{}:{}", EVENT_PREFIX, log));
-//             self.add_trade_history(sale.clone(), buyer_id.clone());
-//
-//             let trade = Promise::new(sale.owner_id).transfer(sale.price.0);
-//             let change = Promise::new(buyer_id).transfer(deposit - sale.price.0);
-//
-//             if deposit > sale.price.0 {
-//                 log!("bid more than sale price, refund change and paying for sale token");
-//                 trade.then(change)
-//             } else {
-//                 log!("bid equals to sale price, just paying for sale token");
-//                 trade
-//             }
-//         }
-//         PromiseResult::Failed => {
-//             log!("Execution `nft_transfer` method was failed. Attached deposit was refund.");
-//             Promise::new(buyer_id).transfer(deposit)
-//         }
-//         PromiseResult::NotReady => unreachable!(),
-//     }
-// }
-//
-// #[private]
-// #[payable]
-// pub fn after_nft_transfer_for_bid(
-//     &mut self,
-//     sale: BidForContract,
-//     owner_id: AccountId,
-// ) -> PromiseOrValue<()> {
-//     match env::promise_result(0) {
-//         PromiseResult::Successful(_) => {
-//             let promise = Promise::new(owner_id).transfer(sale.price.0);
-//             PromiseOrValue::Promise(promise)
-//         }
-//         PromiseResult::Failed => {
-//             log!("Execution `nft_transfer` method was failed. The bidder's token transfer was stopped.");
-//             let mut bids = self.bids.get(&sale.token_id).unwrap_or_else(|| {
-//                 panic_str(
-//                     format!("bids for token is: {}, doesn't exists", sale.token_id).as_str(),
-//                 )
-//             });
-//             bids.push(sale.clone());
-//             self.bids.insert(&sale.token_id, &bids);
-//
-//             PromiseOrValue::Value(())
-//         }
-//         PromiseResult::NotReady => unreachable!(),
-//     }
-// }
-//
-// #[private]
-// #[payable]
-// pub fn after_nft_token(
-//     &mut self,
-//     bidder_id: AccountId,
-//     token_id: TokenId,
-//     #[rustfmt::skip]
-//     #[callback_result]
-//     result: Result<Option<TokenExt>, PromiseError>,
-// ) -> PromiseOrValue<()> {
-//     let bid_price = env::attached_deposit();
-//     let ask_less_bid = self
-//         .asks
-//         .get(&token_id)
-//         .map_or(false, |ask| ask.price.0 <= bid_price);
-//
-//     match result {
-//         Ok(Some(_)) if ask_less_bid => {
-//             log!("ask for current token id less than provided bid, so process purchase");
-//             self.process_purchase(token_id, OrderType::AskLessBid(bidder_id));
-//             PromiseOrValue::Value(())
-//         }
-//         Ok(Some(_)) => {
-//             let new_offer_condition = BidForContract::new(token_id.clone(), bidder_id, bid_price);
-//
-//             match self.bids.get(&token_id).as_mut() {
-//                 Some(offer_conditions) => {
-//                     offer_conditions.push(new_offer_condition);
-//                     self.bids.insert(&token_id, offer_conditions)
-//                 }
-//                 _ => self.bids.insert(&token_id, &vec![new_offer_condition]),
-//             };
-//
-//             PromiseOrValue::Value(())
-//         }
-//         Ok(None) => {
-//             log!(
-//                 "token with id: {} doesn't exist, attached deposit was returned",
-//                 token_id
-//             );
-//             let promise = Promise::new(bidder_id).transfer(bid_price);
-//             PromiseOrValue::Promise(promise)
-//         }
-//         Err(_) => {
-//             log!("`nft_token` execution error was occurred, attached deposit was returned");
-//             let promise = Promise::new(bidder_id).transfer(bid_price);
-//             PromiseOrValue::Promise(promise)
-//         }
-//     }
-// }
-
-// #[near_bindgen]
-// impl NonFungibleTokenApprovalReceiver for Contract {
-//     fn nft_on_approve(
-//         &mut self,
-//         token_id: TokenId,
-//         owner_id: AccountId,
-//         approval_id: u64,
-//         msg: String,
-//     ) -> PromiseOrValue<String> {
-//         require!(env::predecessor_account_id() == self.nft_id);
-//
-//         let SaleArgs { sale_type, price } =
-//             serde_json::from_str(&msg).expect("Couldn't parse json");
-//
-//         match sale_type {
-//             SaleType::AcceptBid => {
-//                 let promise = self.process_purchase(
-//                     token_id,
-//                     OrderType::AcceptBid {
-//                         owner_id,
-//                         approval_id,
-//                     },
-//                 );
-//
-//                 PromiseOrValue::Promise(promise)
-//             }
-//             SaleType::Selling => {
-//                 let price = price.expect("The price didn't provided for selling").0;
-//                 let sale_conditions = AskForContract::new(owner_id, token_id.clone(), approval_id, price);
-//                 self.asks.insert(&token_id, &sale_conditions);
-//                 let ret = json!({
-//                     "status": true,
-//                     "message": format!("token {} with price {} was added to market", token_id, price)
-//                 });
-//
-//                 PromiseOrValue::Value(ret.to_string())
-//             }
-//         }
-//     }
-// }
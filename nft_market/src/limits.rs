@@ -0,0 +1,278 @@
+use crate::consts::DEFAULT_MAX_BIDS_PER_TOKEN;
+use crate::events::{emit_event, BidEvent, MarketEvent};
+use crate::{Contract, ContractAndTokenId, ContractError, ContractExt};
+use battlemon_models::market::bid::BidForContract;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+/// Anti-spam limits of the orders, `None` disables a limit.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OrderLimits {
+    /// Minimum price of a bid in Nears, bids in fungible tokens aren't limited.
+    pub min_bid_price: Option<U128>,
+    /// Maximum number of bids stored for a token, a higher bid replaces the lowest one then.
+    pub max_bids_per_token: Option<u32>,
+    /// Maximum number of open orders of an account: asks, bids, offers, bundles, swaps and rentals.
+    pub max_orders_per_account: Option<u32>,
+    /// A new bid must beat the best bid in the same currency by at least the increment.
+    pub min_bid_increment: Option<U128>,
+}
+
+impl Default for OrderLimits {
+    fn default() -> Self {
+        Self {
+            min_bid_price: None,
+            max_bids_per_token: Some(DEFAULT_MAX_BIDS_PER_TOKEN),
+            max_orders_per_account: None,
+            min_bid_increment: None,
+        }
+    }
+}
+
+impl Contract {
+    /// Check the bid's price against the minimum price and the best bid for the token.
+    pub(crate) fn check_bid_price(
+        &self,
+        key: &ContractAndTokenId,
+        bid: &BidForContract,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Result<(), ContractError> {
+        if let (Some(min_bid_price), None) = (self.order_limits.min_bid_price, ft_contract_id) {
+            if bid.price() < min_bid_price.0 {
+                return Err(ContractError::BidBelowMinPrice(min_bid_price.0));
+            }
+        }
+
+        if let Some(min_bid_increment) = self.order_limits.min_bid_increment {
+            if let Some(best_bid) = self.highest_bid_than_ask(key, ft_contract_id) {
+                let min_price = best_bid.price() + min_bid_increment.0;
+                if bid.price() < min_price {
+                    return Err(ContractError::BidBelowIncrement(min_price));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make room for one more bid for the token.
+    ///
+    /// When the token already has the maximum of bids, the lowest bid in the same currency
+    /// is removed and refunded if the new bid beats it, otherwise the new bid fails.
+    pub(crate) fn make_room_for_bid(
+        &mut self,
+        key: &ContractAndTokenId,
+        bid: &BidForContract,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Result<(), ContractError> {
+        let max_bids = match self.order_limits.max_bids_per_token {
+            Some(max_bids) => max_bids,
+            None => return Ok(()),
+        };
+        let bids = self.bids.get(key).map_or(&[][..], Vec::as_slice);
+        if bids.len() < max_bids as usize {
+            return Ok(());
+        }
+
+        // Bids are stored from the best one, so the last one in the currency is the lowest.
+        let lowest_bid_id = bids
+            .iter()
+            .rev()
+            .find(|other| self.bid_currency(key, &other.id) == ft_contract_id)
+            .filter(|other| other.price() < bid.price())
+            .map(|other| other.id.clone())
+            .ok_or(ContractError::TooManyBids(max_bids))?;
+        if let Some(lowest_bid) = self.remove_bid(key, &lowest_bid_id) {
+            self.refund(lowest_bid.account_id(), lowest_bid.price(), ft_contract_id);
+            emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                &key.0,
+                lowest_bid,
+                ft_contract_id,
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check that the account can open one more order.
+    pub(crate) fn check_orders_per_account(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<(), ContractError> {
+        let max_orders = match self.order_limits.max_orders_per_account {
+            Some(max_orders) => max_orders,
+            None => return Ok(()),
+        };
+        if self.total_orders_by_id(account_id) >= max_orders as usize {
+            return Err(ContractError::TooManyOrders(max_orders));
+        }
+
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Set the anti-spam limits of new orders, the orders already placed are kept.
+    #[handle_result]
+    pub fn set_order_limits(&mut self, order_limits: OrderLimits) -> Result<(), ContractError> {
+        self.check_owner()?;
+        self.order_limits = order_limits;
+        emit_event(MarketEvent::OrderLimitsChanged(self.order_limits.clone()));
+
+        Ok(())
+    }
+
+    pub fn order_limits(&self) -> &OrderLimits {
+        &self.order_limits
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::consts::STORAGE_PER_SALE;
+    use crate::test_utils;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    /// The market with the order limits, its owner `accounts(1)` has deposited for ten orders.
+    fn setup(order_limits: OrderLimits) -> (VMContextBuilder, Contract) {
+        let (context, mut contract) = test_utils::setup(accounts(1), STORAGE_PER_SALE * 10);
+        contract.set_order_limits(order_limits).unwrap();
+
+        (context, contract)
+    }
+
+    #[test]
+    fn order_limits_are_set_only_by_owner() {
+        let (mut context, mut contract) = setup(OrderLimits::default());
+        assert_eq!(contract.order_limits(), &OrderLimits::default());
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.set_order_limits(OrderLimits {
+                max_bids_per_token: None,
+                ..OrderLimits::default()
+            }),
+            Err(ContractError::NotAuthorized(_))
+        ));
+    }
+
+    #[test]
+    fn bid_below_min_price_fails() {
+        let (mut context, mut contract) = setup(OrderLimits {
+            min_bid_price: Some(U128(STORAGE_PER_SALE)),
+            ..OrderLimits::default()
+        });
+
+        context.attached_deposit(STORAGE_PER_SALE - 1);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_bid("1".to_string(), None, None),
+            Err(ContractError::BidBelowMinPrice(price)) if price == STORAGE_PER_SALE
+        ));
+
+        context.attached_deposit(STORAGE_PER_SALE);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+    }
+
+    #[test]
+    fn bid_must_beat_best_bid_by_increment() {
+        let (mut context, mut contract) = setup(OrderLimits {
+            min_bid_increment: Some(U128(100)),
+            ..OrderLimits::default()
+        });
+
+        context.attached_deposit(1_000);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+
+        context.attached_deposit(1_099);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_bid("1".to_string(), None, None),
+            Err(ContractError::BidBelowIncrement(1_100))
+        ));
+
+        context.attached_deposit(1_100);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        // Bids for another token aren't compared.
+        context.attached_deposit(1);
+        testing_env!(context.build());
+        contract.add_bid("2".to_string(), None, None).unwrap();
+    }
+
+    #[test]
+    fn bids_per_token_are_limited() {
+        let (mut context, mut contract) = setup(OrderLimits {
+            max_bids_per_token: Some(2),
+            ..OrderLimits::default()
+        });
+
+        context.attached_deposit(1_000);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        context.attached_deposit(2_000);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        context.attached_deposit(1_000);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_bid("1".to_string(), None, None),
+            Err(ContractError::TooManyBids(2))
+        ));
+        contract.add_bid("2".to_string(), None, None).unwrap();
+
+        // A higher bid replaces the lowest one, which is refunded.
+        context.attached_deposit(1_500);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        let prices: Vec<u128> = contract
+            .bids("1".to_string(), None)
+            .unwrap()
+            .iter()
+            .map(BidForContract::price)
+            .collect();
+        assert_eq!(prices, vec![2_000, 1_500]);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"refund""#)));
+    }
+
+    #[test]
+    fn orders_per_account_are_limited() {
+        let (mut context, mut contract) = setup(OrderLimits {
+            max_orders_per_account: Some(2),
+            ..OrderLimits::default()
+        });
+
+        context.attached_deposit(1_000);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        contract.add_bid("2".to_string(), None, None).unwrap();
+        assert!(matches!(
+            contract.add_bid("3".to_string(), None, None),
+            Err(ContractError::TooManyOrders(2))
+        ));
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.nft_on_approve(
+                "4".to_string(),
+                accounts(1),
+                1,
+                r#"{"price":"100000"}"#.to_string()
+            ),
+            Err(ContractError::TooManyOrders(2))
+        ));
+    }
+}
@@ -0,0 +1,391 @@
+use crate::consts::{NFT_TOKEN_GAS, PLACE_ORDER_GAS};
+use crate::events::{emit_event, MarketEvent};
+use crate::external::nft;
+use crate::helpers::paginate;
+use crate::storage::OrderId;
+use crate::trade::{check_prepaid_gas, trade_gas};
+use crate::{Contract, ContractError, ContractExt, StorageKey};
+use battlemon_models::nft::ModelKind;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{self, Value};
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, near_bindgen, AccountId};
+use std::collections::HashMap;
+
+/// Offer for any token of the NFT contract which meets the filter.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Offer {
+    pub id: u64,
+    pub account_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub price: U128,
+    /// Criteria of the token, `None` for a floor offer which any token of the collection fills.
+    pub filter: Option<TraitFilter>,
+    pub expire_at: Option<u64>,
+}
+
+impl Offer {
+    pub fn is_expired(&self) -> bool {
+        self.expire_at
+            .map_or(false, |expire_at| expire_at <= env::block_timestamp())
+    }
+}
+
+/// Traits which a token must have to fill the offer.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TraitFilter {
+    /// Model kind in snake case, e.g. `lemon` or `fire_arm`.
+    pub kind: String,
+    /// Required values of the model's fields, e.g. `{"lemon_gen": "Nakamoto"}`.
+    #[serde(default)]
+    pub traits: HashMap<String, String>,
+}
+
+impl TraitFilter {
+    pub fn matches(&self, model: &ModelKind) -> bool {
+        let (kind, fields) = match model {
+            ModelKind::Lemon(model) => ("lemon", serde_json::to_value(model)),
+            ModelKind::FireArm(model) => ("fire_arm", serde_json::to_value(model)),
+            ModelKind::ColdArm(model) => ("cold_arm", serde_json::to_value(model)),
+            ModelKind::Cloth(model) => ("cloth", serde_json::to_value(model)),
+            ModelKind::Cap(model) => ("cap", serde_json::to_value(model)),
+            ModelKind::Back(model) => ("back", serde_json::to_value(model)),
+            #[allow(unreachable_patterns)]
+            _ => return false,
+        };
+        if kind != self.kind {
+            return false;
+        }
+
+        let fields = match fields {
+            Ok(fields) => fields,
+            Err(_) => return false,
+        };
+        self.traits
+            .iter()
+            .all(|(name, expected)| match fields.get(name) {
+                Some(Value::String(value)) => value == expected,
+                Some(value) => value.to_string() == *expected,
+                None => false,
+            })
+    }
+}
+
+impl Contract {
+    pub(crate) fn count_offers_for_account(&self, account_id: &AccountId) -> usize {
+        self.offers_by_account
+            .get(account_id)
+            .map_or(0, |ids| ids.len() as usize)
+    }
+
+    /// Store the offer and index it by the maker, the maker's deposit must cover its storage.
+    pub(crate) fn insert_offer(&mut self, offer: Offer) -> Result<(), ContractError> {
+        let account_id = offer.account_id.clone();
+        self.charge_storage(&account_id, OrderId::Offer(offer.id), |this| {
+            this.offers_by_account
+                .entry(offer.account_id.clone())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::OffersByAccountInner {
+                        account_hash: env::sha256(offer.account_id.as_bytes()),
+                    })
+                })
+                .insert(offer.id);
+            this.offers.insert(offer.id, offer);
+        })
+    }
+
+    pub(crate) fn remove_offer(&mut self, id: u64) -> Option<Offer> {
+        let offer = self.offers.remove(&id)?;
+        self.release_storage(&offer.account_id, &OrderId::Offer(id));
+        if let Some(ids) = self.offers_by_account.get_mut(&offer.account_id) {
+            ids.remove(&id);
+            ids.is_empty()
+                .then(|| self.offers_by_account.remove(&offer.account_id));
+        }
+
+        Some(offer)
+    }
+
+    /// Sell the token to the offer's maker.
+    ///
+    /// The offer is taken out of the market while its traits are checked by `nft_token`,
+    /// so it can't be accepted twice. It returns back if the token doesn't fit.
+    pub(crate) fn accept_offer(
+        &mut self,
+        nft_contract_id: AccountId,
+        token_id: TokenId,
+        owner_id: AccountId,
+        approval_id: u64,
+        offer_id: u64,
+    ) -> Result<(), ContractError> {
+        let offer = self
+            .offers
+            .get(&offer_id)
+            .ok_or(ContractError::NotFound("Offer doesn't exist"))?;
+        if offer.nft_contract_id != nft_contract_id {
+            return Err(ContractError::OfferError(
+                "The offer is made for another NFT contract",
+            ));
+        }
+        if offer.is_expired() {
+            return Err(ContractError::OfferError("The offer has expired"));
+        }
+        if offer.account_id == owner_id {
+            return Err(ContractError::OfferError(
+                "The offer can't be accepted by its maker",
+            ));
+        }
+
+        // `on_accept_offer` places the trade like an order method.
+        let after_nft_token_gas = PLACE_ORDER_GAS + trade_gas(None);
+        check_prepaid_gas(NFT_TOKEN_GAS + after_nft_token_gas)?;
+
+        let offer = self.remove_offer(offer_id).unwrap();
+        nft::ext(nft_contract_id)
+            .with_static_gas(NFT_TOKEN_GAS)
+            .nft_token(token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(after_nft_token_gas)
+                    .on_accept_offer(token_id, owner_id, approval_id, offer),
+            );
+
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Make an offer for any token of the NFT contract with the attached deposit as the price.
+    ///
+    /// A floor offer is filled by any token of the collection,
+    /// an offer with `filter` only by tokens with the provided traits.
+    /// The token's owner accepts the offer by `nft_approve` with `{"accept_offer": {"offer_id": 1}}` message.
+    #[payable]
+    #[handle_result]
+    pub fn add_offer(
+        &mut self,
+        nft_contract_id: Option<AccountId>,
+        filter: Option<TraitFilter>,
+        expire_at: Option<u64>,
+    ) -> Result<u64, ContractError> {
+        let nft_contract_id = nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
+        self.check_nft_contract(&nft_contract_id)?;
+        self.check_not_paused()?;
+        if matches!(expire_at, Some(expire_at) if expire_at <= env::block_timestamp()) {
+            return Err(ContractError::OfferError(
+                "The offer's expiration time is already in the past",
+            ));
+        }
+        if env::attached_deposit() == 0 {
+            return Err(ContractError::OfferError(
+                "The offer's price must be attached as a deposit",
+            ));
+        }
+        let account_id = env::predecessor_account_id();
+        self.check_storage_deposits(&account_id)?;
+        self.check_orders_per_account(&account_id)?;
+
+        let offer = Offer {
+            id: self.next_offer_id,
+            account_id,
+            nft_contract_id,
+            price: U128(env::attached_deposit()),
+            filter,
+            expire_at,
+        };
+        self.next_offer_id += 1;
+        emit_event(MarketEvent::AddOffer(offer.clone()));
+        self.insert_offer(offer)?;
+
+        Ok(self.next_offer_id - 1)
+    }
+
+    #[handle_result]
+    pub fn cancel_offer(&mut self, offer_id: u64) -> Result<(), ContractError> {
+        let offer = self
+            .offers
+            .get(&offer_id)
+            .ok_or(ContractError::NotFound("Offer doesn't exist"))?;
+        if offer.account_id != env::predecessor_account_id() {
+            return Err(ContractError::NotAuthorized(
+                "Offer's maker is not the same as the caller",
+            ));
+        }
+
+        let offer = self.remove_offer(offer_id).unwrap();
+        self.refund(&offer.account_id, offer.price.0, None);
+        emit_event(MarketEvent::RemoveOffer(offer));
+
+        Ok(())
+    }
+
+    pub fn offer(&self, offer_id: u64) -> Option<&Offer> {
+        self.offers.get(&offer_id)
+    }
+
+    pub fn offers(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<&Offer> {
+        paginate(self.offers.values(), from_index, limit)
+    }
+
+    pub fn offers_by_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&Offer> {
+        let ids = self
+            .offers_by_account
+            .get(&account_id)
+            .into_iter()
+            .flatten();
+        paginate(ids, from_index, limit)
+            .into_iter()
+            .flat_map(|id| self.offers.get(id))
+            .collect()
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::test_utils::DEPOSIT;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use battlemon_models::helpers_contract::get_random_arr_range;
+    use battlemon_models::nft::{FromTraitWeights, Lemon};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn filter(kind: &str, traits: &[(&str, &str)]) -> TraitFilter {
+        TraitFilter {
+            kind: kind.to_string(),
+            traits: traits
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn trait_filter_matches_model_kind_and_traits() {
+        testing_env!(VMContextBuilder::new().build());
+        let random = get_random_arr_range(0, 100);
+        let model = ModelKind::Lemon(Lemon::from_trait_weights(&"1".to_string(), &random));
+
+        assert!(filter("lemon", &[]).matches(&model));
+        assert!(!filter("fire_arm", &[]).matches(&model));
+        assert!(!filter("lemon", &[("unknown_trait", "1")]).matches(&model));
+    }
+
+    #[test]
+    fn offer_is_cancelled_only_by_maker() {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+        let offer_id = contract
+            .add_offer(None, Some(filter("lemon", &[])), None)
+            .unwrap();
+        assert_eq!(contract.offers(None, None).len(), 1);
+        assert_eq!(contract.count_offers_for_account(&accounts(2)), 1);
+        assert!(matches!(
+            contract.add_offer(None, None, None),
+            Err(ContractError::StorageError(_))
+        ));
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.cancel_offer(offer_id),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.cancel_offer(offer_id).unwrap();
+        assert!(contract.offer(offer_id).is_none());
+        assert!(contract
+            .offers_by_account(accounts(2), None, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn offer_without_deposit_fails() {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+
+        context.attached_deposit(0);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.add_offer(None, None, None),
+            Err(ContractError::OfferError(_))
+        ));
+    }
+
+    #[test]
+    fn returned_offer_is_refunded_without_storage_deposit() {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+        let offer_id = contract.add_offer(None, None, None).unwrap();
+
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        let msg = format!(r#"{{"accept_offer":{{"offer_id":{offer_id}}}}}"#);
+        contract
+            .nft_on_approve("1".to_string(), accounts(3), 1, msg)
+            .unwrap();
+        assert!(contract.offer(offer_id).is_none());
+
+        // The maker withdraws the deposit released while the token is checked.
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1);
+        testing_env!(context.build());
+        contract.storage_withdraw(None).unwrap();
+
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        let offer = Offer {
+            id: offer_id,
+            account_id: accounts(2),
+            nft_contract_id: accounts(0),
+            price: U128(DEPOSIT),
+            filter: None,
+            expire_at: None,
+        };
+        contract.on_accept_offer("1".to_string(), accounts(3), 1, offer, Ok(None));
+        assert!(contract.offer(offer_id).is_none());
+        assert_eq!(contract.count_offers_for_account(&accounts(2)), 0);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::bid::is_expired;
+use crate::consts::MAX_COLLECTION_BOOK_TOKENS;
+use crate::helpers::paginate;
+use crate::{Contract, ContractAndTokenId, ContractExt, StorageKey};
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, near_bindgen, AccountId, Balance};
+use std::collections::BTreeMap;
+
+/// Orders at the same price.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceLevel {
+    pub price: U128,
+    pub orders: u64,
+}
+
+/// Best prices and depth of the public bids and asks in one currency.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OrderBook {
+    pub best_bid: Option<U128>,
+    pub best_ask: Option<U128>,
+    /// The best ask minus the best bid, zero if they cross.
+    pub spread: Option<U128>,
+    /// Price levels from the highest bid.
+    pub bids: Vec<PriceLevel>,
+    /// Price levels from the lowest ask.
+    pub asks: Vec<PriceLevel>,
+}
+
+impl OrderBook {
+    fn new(
+        bid_prices: impl Iterator<Item = Balance>,
+        ask_prices: impl Iterator<Item = Balance>,
+        depth: Option<u64>,
+    ) -> Self {
+        let depth = depth.map_or(usize::MAX, |depth| depth as usize);
+        let bids = price_levels(bid_prices);
+        let asks = price_levels(ask_prices);
+        let best_bid = bids.keys().next_back().copied();
+        let best_ask = asks.keys().next().copied();
+        let spread = best_bid
+            .zip(best_ask)
+            .map(|(bid, ask)| U128(ask.saturating_sub(bid)));
+
+        Self {
+            best_bid: best_bid.map(U128),
+            best_ask: best_ask.map(U128),
+            spread,
+            bids: bids
+                .into_iter()
+                .rev()
+                .take(depth)
+                .map(PriceLevel::from)
+                .collect(),
+            asks: asks.into_iter().take(depth).map(PriceLevel::from).collect(),
+        }
+    }
+}
+
+impl From<(Balance, u64)> for PriceLevel {
+    fn from((price, orders): (Balance, u64)) -> Self {
+        Self {
+            price: U128(price),
+            orders,
+        }
+    }
+}
+
+fn price_levels(prices: impl Iterator<Item = Balance>) -> BTreeMap<Balance, u64> {
+    prices.fold(BTreeMap::new(), |mut levels, price| {
+        *levels.entry(price).or_default() += 1;
+        levels
+    })
+}
+
+impl Contract {
+    /// Index the token of a new ask or bid by its NFT contract.
+    pub(crate) fn index_collection_token(&mut self, key: &ContractAndTokenId) {
+        let (nft_contract_id, token_id) = key;
+        self.tokens_by_collection
+            .entry(nft_contract_id.to_owned())
+            .or_insert_with(|| {
+                UnorderedSet::new(StorageKey::TokensByCollectionInner {
+                    account_hash: env::sha256(nft_contract_id.as_bytes()),
+                })
+            })
+            .insert(token_id.to_owned());
+    }
+
+    /// Drop the token from the index once it has neither an ask nor bids.
+    pub(crate) fn unindex_collection_token(&mut self, key: &ContractAndTokenId) {
+        if self.asks.contains_key(key) || self.bids.contains_key(key) {
+            return;
+        }
+
+        let (nft_contract_id, token_id) = key;
+        if let Some(token_ids) = self.tokens_by_collection.get_mut(nft_contract_id) {
+            token_ids.remove(token_id);
+            token_ids
+                .is_empty()
+                .then(|| self.tokens_by_collection.remove(nft_contract_id));
+        }
+    }
+
+    /// Current price of the ask if it can be filled in the currency by anyone.
+    ///
+    /// Private asks and English auctions aren't in the order book.
+    fn book_ask_price(
+        &self,
+        key: &ContractAndTokenId,
+        ask: &AskForContract,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Option<Balance> {
+        if self.is_private_ask(key)
+            || self.auctions.contains_key(key)
+            || self.ask_currency(key) != ft_contract_id
+        {
+            return None;
+        }
+
+        Some(self.ask_price(key, ask))
+    }
+
+    /// Prices of the not expired bids in the currency, from the highest one.
+    fn book_bid_prices<'a>(
+        &'a self,
+        key: &'a ContractAndTokenId,
+        bids: &'a [BidForContract],
+        ft_contract_id: Option<&'a AccountId>,
+    ) -> impl Iterator<Item = Balance> + 'a {
+        bids.iter()
+            .filter(|bid| !is_expired(bid))
+            .filter(move |bid| self.bid_currency(key, &bid.id) == ft_contract_id)
+            .map(BidForContract::price)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// The highest bid for the token in Nears or, if `ft_contract_id` is provided, in the fungible token.
+    pub fn best_bid(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        ft_contract_id: Option<AccountId>,
+    ) -> Option<BidForContract> {
+        let key = self.key(nft_contract_id, token_id);
+        self.highest_bid_than_ask(&key, ft_contract_id.as_ref())
+    }
+
+    /// The token's ask at its current price, if it's public and in the currency.
+    pub fn best_ask(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        ft_contract_id: Option<AccountId>,
+    ) -> Option<AskForContract> {
+        let key = self.key(nft_contract_id, token_id);
+        let ask = self.asks.get(&key)?;
+        let price = self.book_ask_price(&key, ask, ft_contract_id.as_ref())?;
+        let mut ask = ask.to_owned();
+        ask.price = U128(price);
+
+        Some(ask)
+    }
+
+    /// Best prices, spread and up to `depth` price levels on each side for the token.
+    pub fn order_book(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        ft_contract_id: Option<AccountId>,
+        depth: Option<u64>,
+    ) -> OrderBook {
+        let key = self.key(nft_contract_id, token_id);
+        let ft_contract_id = ft_contract_id.as_ref();
+        let bids = self.bids.get(&key).map_or(&[][..], Vec::as_slice);
+        let ask_price = self
+            .asks
+            .get(&key)
+            .and_then(|ask| self.book_ask_price(&key, ask, ft_contract_id));
+
+        OrderBook::new(
+            self.book_bid_prices(&key, bids, ft_contract_id),
+            ask_price.into_iter(),
+            depth,
+        )
+    }
+
+    /// Best prices, spread and up to `depth` price levels on each side
+    /// for a page of the NFT contract's tokens with asks or bids.
+    ///
+    /// A page has up to `MAX_COLLECTION_BOOK_TOKENS` tokens, clients merge the pages
+    /// up to `total_collection_book_tokens` for the whole collection.
+    pub fn collection_order_book(
+        &self,
+        nft_contract_id: Option<AccountId>,
+        ft_contract_id: Option<AccountId>,
+        depth: Option<u64>,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> OrderBook {
+        let nft_contract_id = nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
+        let ft_contract_id = ft_contract_id.as_ref();
+        let limit = limit.map_or(MAX_COLLECTION_BOOK_TOKENS, |limit| {
+            limit.min(MAX_COLLECTION_BOOK_TOKENS)
+        });
+        let keys: Vec<ContractAndTokenId> = self
+            .tokens_by_collection
+            .get(&nft_contract_id)
+            .map_or_else(Vec::new, |token_ids| {
+                paginate(token_ids.iter(), from_index, Some(limit))
+            })
+            .into_iter()
+            .map(|token_id| (nft_contract_id.clone(), token_id.to_owned()))
+            .collect();
+        let bid_prices = keys.iter().flat_map(|key| {
+            let bids = self.bids.get(key).map_or(&[][..], Vec::as_slice);
+            self.book_bid_prices(key, bids, ft_contract_id)
+        });
+        let ask_prices = keys.iter().filter_map(|key| {
+            let ask = self.asks.get(key)?;
+            self.book_ask_price(key, ask, ft_contract_id)
+        });
+
+        OrderBook::new(bid_prices, ask_prices, depth)
+    }
+
+    /// Number of the NFT contract's tokens with asks or bids.
+    pub fn total_collection_book_tokens(&self, nft_contract_id: Option<AccountId>) -> u64 {
+        let nft_contract_id = nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
+        self.tokens_by_collection
+            .get(&nft_contract_id)
+            .map_or(0, |token_ids| token_ids.len() as u64)
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::consts::STORAGE_PER_SALE;
+    use crate::test_utils::get_context;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn place_bids(contract: &mut Contract, context: &mut VMContextBuilder, prices: &[u128]) {
+        for price in prices {
+            context.attached_deposit(STORAGE_PER_SALE * price);
+            testing_env!(context.build());
+            contract.storage_deposit(None, None).unwrap();
+            contract.add_bid("1".to_string(), None, None).unwrap();
+        }
+    }
+
+    #[test]
+    fn bids_are_aggregated_by_price_from_the_highest() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        place_bids(&mut contract, &mut context, &[1, 3, 2, 3]);
+
+        let best_bid = contract.best_bid("1".to_string(), None, None).unwrap();
+        assert_eq!(best_bid.price(), STORAGE_PER_SALE * 3);
+        let prices: Vec<_> = contract
+            .bids_for_token("1".to_string(), None, None, None)
+            .iter()
+            .map(|bid| bid.price() / STORAGE_PER_SALE)
+            .collect();
+        assert_eq!(prices, vec![3, 3, 2, 1]);
+
+        let book = contract.order_book("1".to_string(), None, None, Some(2));
+        assert_eq!(book.best_bid, Some(U128(STORAGE_PER_SALE * 3)));
+        assert!(book.best_ask.is_none() && book.spread.is_none());
+        assert_eq!(
+            book.bids,
+            vec![
+                PriceLevel::from((STORAGE_PER_SALE * 3, 2)),
+                PriceLevel::from((STORAGE_PER_SALE * 2, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn spread_is_between_best_ask_and_best_bid() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        place_bids(&mut contract, &mut context, &[1, 2]);
+
+        let mut context = get_context(accounts(0));
+        context.attached_deposit(STORAGE_PER_SALE);
+        testing_env!(context.build());
+        contract.storage_deposit(Some(accounts(2)), None).unwrap();
+        context.attached_deposit(0);
+        testing_env!(context.build());
+        let msg = format!("{{\"price\":\"{}\"}}", STORAGE_PER_SALE * 5);
+        contract
+            .nft_on_approve("1".to_string(), accounts(2), 1, msg)
+            .unwrap();
+
+        let best_ask = contract.best_ask("1".to_string(), None, None).unwrap();
+        assert_eq!(best_ask.price, U128(STORAGE_PER_SALE * 5));
+        assert!(contract
+            .best_ask("1".to_string(), None, Some(accounts(3)))
+            .is_none());
+
+        let book = contract.collection_order_book(None, None, None, None, None);
+        assert_eq!(book.best_ask, Some(U128(STORAGE_PER_SALE * 5)));
+        assert_eq!(book.spread, Some(U128(STORAGE_PER_SALE * 3)));
+        assert_eq!(book.asks, vec![PriceLevel::from((STORAGE_PER_SALE * 5, 1))]);
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(
+            contract.collection_order_book(Some(accounts(3)), None, None, None, None),
+            OrderBook::new(std::iter::empty(), std::iter::empty(), None)
+        );
+    }
+
+    #[test]
+    fn collection_order_book_pages_through_tokens_with_orders() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(STORAGE_PER_SALE * 10);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+        for (token_id, price) in [("1", 1), ("2", 2), ("3", 3)] {
+            context.attached_deposit(STORAGE_PER_SALE * price);
+            testing_env!(context.build());
+            contract.add_bid(token_id.to_string(), None, None).unwrap();
+        }
+        assert_eq!(contract.total_collection_book_tokens(None), 3);
+
+        let first_page = contract.collection_order_book(None, None, None, None, Some(2));
+        let second_page = contract.collection_order_book(None, None, None, Some(U128(2)), Some(2));
+        assert_eq!(first_page.bids.len() + second_page.bids.len(), 3);
+        assert_eq!(second_page.bids.len(), 1);
+
+        let bid_id = contract.bids("2".to_string(), None).unwrap()[0].id.clone();
+        contract.cancel_bid("2".to_string(), bid_id, None).unwrap();
+        assert_eq!(contract.total_collection_book_tokens(None), 2);
+        assert_eq!(contract.total_collection_book_tokens(Some(accounts(3))), 0);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::consts::{BASIS_POINTS, MAX_LEN_PAYOUT, MAX_MARKET_FEE};
+use crate::external::Payout;
+use crate::refund::pay;
+use crate::ContractError;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, Balance};
+use std::collections::HashMap;
+
+/// Market fee in basis points and the treasury account which receives it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeeConfig {
+    pub fee: u16,
+    pub treasury_id: AccountId,
+}
+
+impl FeeConfig {
+    pub fn new(fee: u16, treasury_id: AccountId) -> Result<Self, ContractError> {
+        if fee > MAX_MARKET_FEE {
+            return Err(ContractError::FeeError(
+                "The fee exceeds the maximum allowed basis points",
+            ));
+        }
+
+        Ok(Self { fee, treasury_id })
+    }
+
+    pub fn calculate(&self, price: Balance) -> Balance {
+        price * self.fee as u128 / BASIS_POINTS as u128
+    }
+}
+
+/// Pay out `balance` in Nears or in the fungible token
+/// according to the payout returned by the NFT contract.
+///
+/// Everything which isn't claimed by the payout goes to the seller.
+/// If the payout is invalid, the whole balance goes to the seller.
+/// `seller_charge` is kept by the market from the seller's share in both cases,
+/// royalty holders are paid in full. Failed payments in fungible tokens are claimable.
+/// Returns the amount paid to royalty holders.
+pub(crate) fn distribute_payout(
+    seller_id: &AccountId,
+    balance: Balance,
+    seller_charge: Balance,
+    payout: Payout,
+    ft_contract_id: Option<&AccountId>,
+) -> Balance {
+    let total = match payout_total(&payout, balance) {
+        Some(total) => total,
+        None => {
+            let to_seller = balance.saturating_sub(seller_charge);
+            if to_seller > 0 {
+                pay(ft_contract_id, seller_id, to_seller);
+            }
+            return 0;
+        }
+    };
+
+    let mut royalty = 0;
+    let mut to_seller = balance - total;
+    for (account_id, amount) in payout.payout {
+        if account_id == *seller_id {
+            to_seller += amount.0;
+        } else if amount.0 > 0 {
+            royalty += amount.0;
+            pay(ft_contract_id, &account_id, amount.0);
+        }
+    }
+
+    let to_seller = to_seller.saturating_sub(seller_charge);
+    if to_seller > 0 {
+        pay(ft_contract_id, seller_id, to_seller);
+    }
+
+    royalty
+}
+
+/// Merge the payouts of a lot's tokens, every token is paid out of its own share of the price.
+///
+/// The tokens are held by the market when their payouts are requested,
+/// so the market's part is the seller's one.
+/// A token without a valid payout gives its whole share to the seller.
+pub(crate) fn merge_payouts(
+    seller_id: &AccountId,
+    shares: &[Balance],
+    payouts: Vec<Option<Payout>>,
+) -> Payout {
+    let mut merged: HashMap<AccountId, U128> = HashMap::new();
+    let mut add = |account_id: AccountId, amount: Balance| {
+        let account_id = if account_id == env::current_account_id() {
+            seller_id.to_owned()
+        } else {
+            account_id
+        };
+        merged.entry(account_id).or_insert(U128(0)).0 += amount;
+    };
+
+    for (share, payout) in shares.iter().zip(payouts) {
+        match payout.filter(|payout| payout_total(payout, *share).is_some()) {
+            Some(payout) => {
+                for (account_id, amount) in payout.payout {
+                    add(account_id, amount.0);
+                }
+            }
+            None => add(seller_id.to_owned(), *share),
+        }
+    }
+
+    Payout { payout: merged }
+}
+
+/// Total amount of the payout, `None` if it's invalid for the balance.
+fn payout_total(payout: &Payout, balance: Balance) -> Option<Balance> {
+    if payout.payout.len() as u32 > MAX_LEN_PAYOUT {
+        return None;
+    }
+
+    payout
+        .payout
+        .values()
+        .try_fold(0u128, |acc, amount| acc.checked_add(amount.0))
+        .filter(|total| *total <= balance)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn distribute_payout_returns_royalty() {
+        testing_env!(VMContextBuilder::new().build());
+        let payout = Payout {
+            payout: HashMap::from([(accounts(1), U128(900)), (accounts(2), U128(100))]),
+        };
+
+        assert_eq!(distribute_payout(&accounts(1), 1_000, 0, payout, None), 100);
+    }
+
+    #[test]
+    fn distribute_payout_charges_only_seller() {
+        testing_env!(VMContextBuilder::new().build());
+        // The payout covers the whole balance, the charge doesn't make it invalid.
+        let payout = Payout {
+            payout: HashMap::from([(accounts(1), U128(900)), (accounts(2), U128(100))]),
+        };
+
+        assert_eq!(
+            distribute_payout(&accounts(1), 1_000, 50, payout, None),
+            100
+        );
+    }
+
+    #[test]
+    fn distribute_payout_ignores_payout_which_exceeds_balance() {
+        testing_env!(VMContextBuilder::new().build());
+        let payout = Payout {
+            payout: HashMap::from([(accounts(1), U128(900)), (accounts(2), U128(200))]),
+        };
+
+        assert_eq!(distribute_payout(&accounts(1), 1_000, 0, payout, None), 0);
+    }
+
+    #[test]
+    fn merged_payouts_give_market_part_and_invalid_shares_to_seller() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .build());
+        let payout = Payout {
+            payout: HashMap::from([(accounts(0), U128(450)), (accounts(2), U128(50))]),
+        };
+        let invalid = Payout {
+            payout: HashMap::from([(accounts(2), U128(501))]),
+        };
+
+        let merged = merge_payouts(
+            &accounts(1),
+            &[500, 500, 500],
+            vec![Some(payout), Some(invalid), None],
+        );
+        assert_eq!(
+            merged.payout,
+            HashMap::from([(accounts(1), U128(1_450)), (accounts(2), U128(50))])
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use crate::consts::AFTER_REFUND_GAS;
+use crate::currency::transfer;
+use crate::events::{emit_event, MarketEvent, Refund};
+use near_sdk::json_types::U128;
+use near_sdk::{env, AccountId, Balance, Promise};
+
+impl crate::Contract {
+    /// Return escrowed Nears or fungible tokens of `ft_contract_id` to the account.
+    ///
+    /// If the transfer fails, the amount is credited to the account's
+    /// claimable balance, so it can be withdrawn later with `claim_balance`.
+    pub(crate) fn refund(
+        &self,
+        account_id: &AccountId,
+        amount: Balance,
+        ft_contract_id: Option<&AccountId>,
+    ) -> Promise {
+        emit_event(MarketEvent::Refund(Refund {
+            account_id: account_id.to_owned(),
+            amount: U128(amount),
+            ft_contract_id: ft_contract_id.cloned(),
+        }));
+        credit_on_failure(
+            transfer(ft_contract_id, account_id, amount),
+            account_id,
+            amount,
+            ft_contract_id,
+        )
+    }
+}
+
+/// Pay `amount` to the account in Nears or in the fungible token of `ft_contract_id`.
+///
+/// `ft_transfer` fails for an account which isn't registered with the fungible token,
+/// then the amount is credited to the account's claimable balance like a failed refund.
+pub(crate) fn pay(
+    ft_contract_id: Option<&AccountId>,
+    account_id: &AccountId,
+    amount: Balance,
+) -> Promise {
+    let payment = transfer(ft_contract_id, account_id, amount);
+    match ft_contract_id {
+        None => payment,
+        Some(_) => credit_on_failure(payment, account_id, amount, ft_contract_id),
+    }
+}
+
+fn credit_on_failure(
+    payment: Promise,
+    account_id: &AccountId,
+    amount: Balance,
+    ft_contract_id: Option<&AccountId>,
+) -> Promise {
+    payment.then(
+        crate::Contract::ext(env::current_account_id())
+            .with_static_gas(AFTER_REFUND_GAS)
+            .on_refund(account_id.to_owned(), U128(amount), ft_contract_id.cloned()),
+    )
+}
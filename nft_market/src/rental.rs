@@ -0,0 +1,451 @@
+use crate::consts::{AFTER_SET_USER_GAS, NFT_SET_USER_GAS, ONE_YOCTO};
+use crate::currency::transfer;
+use crate::events::{emit_event, MarketEvent, RentalEnded};
+use crate::external::nft;
+use crate::helpers::paginate;
+use crate::storage::OrderId;
+use crate::{Contract, ContractAndTokenId, ContractError, ContractExt, StorageKey};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, near_bindgen, AccountId};
+
+/// Lending order: the renter becomes the token's user for the rented periods, the owner keeps the token.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Rental {
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub approval_id: u64,
+    /// Nears for one period.
+    pub price_per_period: U128,
+    /// Length of one period in nanoseconds.
+    pub period: u64,
+    /// The most periods which can be rented at once.
+    pub max_periods: u64,
+    /// The current lease, it's kept after the expiration until the owner is paid.
+    pub lease: Option<Lease>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Lease {
+    pub renter_id: AccountId,
+    /// Timestamp in nanoseconds.
+    pub expires_at: u64,
+    /// Nears held by the market until the lease ends.
+    pub rent: U128,
+}
+
+impl Rental {
+    pub(crate) fn is_leased(&self) -> bool {
+        self.lease.is_some()
+    }
+
+    fn is_rented(&self) -> bool {
+        matches!(&self.lease, Some(lease) if lease.expires_at > env::block_timestamp())
+    }
+}
+
+impl Contract {
+    pub(crate) fn count_rentals_for_account(&self, account_id: &AccountId) -> usize {
+        self.rentals_by_owner
+            .get(account_id)
+            .map_or(0, |keys| keys.len() as usize)
+    }
+
+    pub(crate) fn is_lent_by(&self, key: &ContractAndTokenId, account_id: &AccountId) -> bool {
+        matches!(self.rentals.get(key), Some(rental) if &rental.owner_id == account_id)
+    }
+
+    /// List the token for rent, the owner's re-approval updates the terms and keeps the lease.
+    pub(crate) fn add_rental(&mut self, rental: Rental) -> Result<(), ContractError> {
+        if rental.period == 0 || rental.max_periods == 0 {
+            return Err(ContractError::RentalError(
+                "The rental period must be positive",
+            ));
+        }
+
+        let key = (rental.nft_contract_id.clone(), rental.token_id.clone());
+        self.end_lease(&key);
+        match self.rentals.get_mut(&key) {
+            Some(prev) if prev.owner_id == rental.owner_id => {
+                *prev = Rental {
+                    lease: prev.lease.take(),
+                    ..rental
+                };
+                emit_event(MarketEvent::AddRental(prev.clone()));
+                return Ok(());
+            }
+            Some(prev) if prev.is_leased() => {
+                return Err(ContractError::RentalError("The token is rented"));
+            }
+            _ => {}
+        }
+
+        if let Some(prev) = self.remove_rental(&key) {
+            emit_event(MarketEvent::RemoveRental(prev));
+        }
+        emit_event(MarketEvent::AddRental(rental.clone()));
+        self.insert_rental(rental)
+    }
+
+    /// Store the rental and index it by the owner.
+    fn insert_rental(&mut self, rental: Rental) -> Result<(), ContractError> {
+        let key = (rental.nft_contract_id.clone(), rental.token_id.clone());
+        let account_id = rental.owner_id.clone();
+        self.charge_storage(&account_id, OrderId::Rental(key.clone()), |this| {
+            this.rentals_by_owner
+                .entry(rental.owner_id.clone())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::RentalsByOwnerInner {
+                        account_hash: env::sha256(rental.owner_id.as_bytes()),
+                    })
+                })
+                .insert(key.clone());
+            this.rentals.insert(key, rental);
+        })
+    }
+
+    pub(crate) fn remove_rental(&mut self, key: &ContractAndTokenId) -> Option<Rental> {
+        let rental = self.rentals.remove(key)?;
+        self.release_storage(&rental.owner_id, &OrderId::Rental(key.to_owned()));
+        if let Some(keys) = self.rentals_by_owner.get_mut(&rental.owner_id) {
+            keys.remove(key);
+            keys.is_empty()
+                .then(|| self.rentals_by_owner.remove(&rental.owner_id));
+        }
+
+        Some(rental)
+    }
+
+    /// Pay the owner the rent of the expired lease without the market fee.
+    ///
+    /// Returns whether the lease was ended.
+    pub(crate) fn end_lease(&mut self, key: &ContractAndTokenId) -> bool {
+        let rental = match self.rentals.get_mut(key) {
+            Some(rental) if rental.is_leased() && !rental.is_rented() => rental,
+            _ => return false,
+        };
+        let ended = rental.clone();
+        let lease = rental.lease.take().unwrap();
+
+        let market_fee = self.fee_config.calculate(lease.rent.0);
+        if market_fee > 0 {
+            transfer(None, &self.fee_config.treasury_id, market_fee);
+        }
+        if lease.rent.0 > market_fee {
+            transfer(None, &ended.owner_id, lease.rent.0 - market_fee);
+        }
+        emit_event(MarketEvent::RentalEnded(RentalEnded {
+            rental: ended,
+            market_fee: U128(market_fee),
+        }));
+
+        true
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rent the token for `periods` periods, the attached deposit must cover the rent.
+    ///
+    /// The rent is held by the market until the lease ends, the change is returned
+    /// after the NFT contract makes the caller the token's user.
+    #[payable]
+    #[handle_result]
+    pub fn rent(
+        &mut self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        periods: u64,
+    ) -> Result<(), ContractError> {
+        self.check_not_paused()?;
+        let key = self.key(nft_contract_id, token_id);
+        self.end_lease(&key);
+        let rental = self
+            .rentals
+            .get(&key)
+            .ok_or(ContractError::NotFound("Rental doesn't exist"))?;
+        let renter_id = env::predecessor_account_id();
+        if rental.owner_id == renter_id {
+            return Err(ContractError::RentalError("The owner can't rent own token"));
+        }
+        if rental.is_leased() {
+            return Err(ContractError::RentalError("The token is rented"));
+        }
+        if periods == 0 || periods > rental.max_periods {
+            return Err(ContractError::RentalError(
+                "The number of periods is out of the rental's limits",
+            ));
+        }
+        let rent = rental
+            .price_per_period
+            .0
+            .checked_mul(u128::from(periods))
+            .ok_or(ContractError::RentalError("The rent overflows"))?;
+        let deposit = env::attached_deposit();
+        if deposit < rent {
+            return Err(ContractError::RentalError(
+                "The attached deposit is less than the rent",
+            ));
+        }
+        let expires_at = rental
+            .period
+            .checked_mul(periods)
+            .and_then(|duration| env::block_timestamp().checked_add(duration))
+            .ok_or(ContractError::RentalError("The lease is too long"))?;
+
+        let approval_id = rental.approval_id;
+        // The lease is stored at once, so the token can't be rented twice while the user is set.
+        self.rentals.get_mut(&key).unwrap().lease = Some(Lease {
+            renter_id: renter_id.clone(),
+            expires_at,
+            rent: U128(rent),
+        });
+        nft::ext(key.0.clone())
+            .with_attached_deposit(ONE_YOCTO)
+            .with_static_gas(NFT_SET_USER_GAS)
+            .nft_set_user(
+                key.1.clone(),
+                renter_id.clone(),
+                expires_at,
+                Some(approval_id),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(AFTER_SET_USER_GAS)
+                    .on_rent(key.0, key.1, renter_id, U128(deposit)),
+            );
+
+        Ok(())
+    }
+
+    /// Pay the owner for the expired lease, anyone can call it.
+    #[handle_result]
+    pub fn end_rental(
+        &mut self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        let key = self.key(nft_contract_id, token_id);
+        if !self.rentals.contains_key(&key) {
+            return Err(ContractError::NotFound("Rental doesn't exist"));
+        }
+        if !self.end_lease(&key) {
+            return Err(ContractError::RentalError(
+                "The token isn't leased or the lease hasn't expired yet",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Stop lending the token, the owner of a rented token has to wait until the lease expires.
+    #[handle_result]
+    pub fn cancel_rental(
+        &mut self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> Result<(), ContractError> {
+        let key = self.key(nft_contract_id, token_id);
+        if !self.is_lent_by(&key, &env::predecessor_account_id()) {
+            return Err(ContractError::NotAuthorized(
+                "Rental's owner is not the same as the caller",
+            ));
+        }
+        self.end_lease(&key);
+        if self.rentals.get(&key).map_or(false, Rental::is_leased) {
+            return Err(ContractError::RentalError("The token is rented"));
+        }
+
+        let rental = self.remove_rental(&key).unwrap();
+        emit_event(MarketEvent::RemoveRental(rental));
+
+        Ok(())
+    }
+
+    pub fn rental(&self, token_id: TokenId, nft_contract_id: Option<AccountId>) -> Option<&Rental> {
+        self.rentals.get(&self.key(nft_contract_id, token_id))
+    }
+
+    pub fn rentals(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<&Rental> {
+        paginate(self.rentals.values(), from_index, limit)
+    }
+
+    pub fn rentals_by_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&Rental> {
+        let keys = self.rentals_by_owner.get(&account_id).into_iter().flatten();
+        paginate(keys, from_index, limit)
+            .into_iter()
+            .flat_map(|key| self.rentals.get(key))
+            .collect()
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::consts::STORAGE_PER_SALE;
+    use crate::test_utils::{setup, DEPOSIT};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn rental(owner_id: AccountId) -> Rental {
+        Rental {
+            nft_contract_id: accounts(0),
+            token_id: "1".to_string(),
+            owner_id,
+            approval_id: 1,
+            price_per_period: U128(10),
+            period: 100,
+            max_periods: 3,
+            lease: None,
+        }
+    }
+
+    /// Alice, `accounts(2)`, lends token "1" for 10 per period of 100, up to 3 periods.
+    fn lend_token() -> (VMContextBuilder, Contract) {
+        let (context, mut contract) = setup(accounts(2), DEPOSIT);
+        contract.add_rental(rental(accounts(2))).unwrap();
+
+        (context, contract)
+    }
+
+    #[test]
+    fn rent_takes_lease_until_it_expires() {
+        let (mut context, mut contract) = lend_token();
+        assert_eq!(contract.count_rentals_for_account(&accounts(2)), 1);
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(30);
+        testing_env!(context.build());
+        contract.rent("1".to_string(), None, 3).unwrap();
+        let lease = contract
+            .rental("1".to_string(), None)
+            .unwrap()
+            .lease
+            .clone();
+        assert_eq!(
+            lease,
+            Some(Lease {
+                renter_id: accounts(3),
+                expires_at: 300,
+                rent: U128(30),
+            })
+        );
+
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.rent("1".to_string(), None, 1),
+            Err(ContractError::RentalError(_))
+        ));
+        assert!(matches!(
+            contract.end_rental("1".to_string(), None),
+            Err(ContractError::RentalError(_))
+        ));
+
+        context.block_timestamp(300);
+        testing_env!(context.build());
+        contract.end_rental("1".to_string(), None).unwrap();
+        assert!(contract
+            .rental("1".to_string(), None)
+            .unwrap()
+            .lease
+            .is_none());
+    }
+
+    #[test]
+    fn rent_fails_out_of_rental_terms() {
+        let (mut context, mut contract) = lend_token();
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(20);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.rent("1".to_string(), None, 4),
+            Err(ContractError::RentalError(_))
+        ));
+        assert!(matches!(
+            contract.rent("1".to_string(), None, 3),
+            Err(ContractError::RentalError(_))
+        ));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.rent("1".to_string(), None, 1),
+            Err(ContractError::RentalError(_))
+        ));
+    }
+
+    #[test]
+    fn rented_token_is_cancelled_after_lease_ends() {
+        let (mut context, mut contract) = lend_token();
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(10);
+        testing_env!(context.build());
+        contract.rent("1".to_string(), None, 1).unwrap();
+
+        assert!(matches!(
+            contract.cancel_rental("1".to_string(), None),
+            Err(ContractError::NotAuthorized(_))
+        ));
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.cancel_rental("1".to_string(), None),
+            Err(ContractError::RentalError(_))
+        ));
+
+        context.block_timestamp(100);
+        testing_env!(context.build());
+        contract.cancel_rental("1".to_string(), None).unwrap();
+        assert!(contract.rentals(None, None).is_empty());
+        assert!(contract
+            .rentals_by_owner(accounts(2), None, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn re_lending_updates_terms_and_keeps_lease() {
+        let (mut context, mut contract) = lend_token();
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(10);
+        testing_env!(context.build());
+        contract.rent("1".to_string(), None, 1).unwrap();
+
+        contract
+            .add_rental(Rental {
+                approval_id: 2,
+                price_per_period: U128(STORAGE_PER_SALE),
+                ..rental(accounts(2))
+            })
+            .unwrap();
+        let lent = contract.rental("1".to_string(), None).unwrap().clone();
+        assert_eq!(lent.approval_id, 2);
+        assert_eq!(lent.price_per_period, U128(STORAGE_PER_SALE));
+        assert!(lent.lease.is_some());
+        assert_eq!(contract.count_rentals_for_account(&accounts(2)), 1);
+
+        assert!(matches!(
+            contract.add_rental(Rental {
+                owner_id: accounts(4),
+                ..lent
+            }),
+            Err(ContractError::RentalError(_))
+        ));
+    }
+}
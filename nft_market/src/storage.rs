@@ -0,0 +1,421 @@
+use crate::consts::STORAGE_PER_SALE;
+use crate::events::{emit_event, BidEvent, MarketEvent, StorageChange};
+use crate::{check_one_yocto, Contract, ContractAndTokenId, ContractError, ContractExt, Rental};
+use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Balance, Promise};
+use std::ops::AddAssign;
+
+/// Parts of the account's storage deposit which are used and not used by its orders.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageUsage {
+    pub used: U128,
+    pub available: U128,
+}
+
+/// Order which is charged for the storage it takes.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum OrderId {
+    Ask(ContractAndTokenId),
+    Bid(ContractAndTokenId, String),
+    Offer(u64),
+    Bundle(u64),
+    Swap(u64),
+    Rental(ContractAndTokenId),
+}
+
+impl Contract {
+    /// Run `insert` and charge the account for the bytes the order has taken.
+    ///
+    /// Collections cache their changes, so they're flushed before
+    /// and after `insert` to measure the `env::storage_usage` delta.
+    /// Fails if the account's deposit doesn't cover the charge, the caller must not keep the order then.
+    pub(crate) fn charge_storage(
+        &mut self,
+        account_id: &AccountId,
+        order_id: OrderId,
+        insert: impl FnOnce(&mut Self),
+    ) -> Result<(), ContractError> {
+        self.flush_orders(account_id, &order_id);
+        let initial_storage_usage = env::storage_usage();
+
+        insert(self);
+        // The records of the charge are a part of the order's storage.
+        self.order_storage.insert(order_id.clone(), 0);
+        self.storage_used.entry(account_id.to_owned()).or_default();
+        self.flush_orders(account_id, &order_id);
+
+        let bytes = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.order_storage.insert(order_id, bytes);
+        self.storage_used
+            .entry(account_id.to_owned())
+            .or_default()
+            .add_assign(bytes);
+
+        self.check_storage_used(account_id)
+    }
+
+    /// Release the storage charged for the order.
+    pub(crate) fn release_storage(&mut self, account_id: &AccountId, order_id: &OrderId) {
+        let bytes = match self.order_storage.remove(order_id) {
+            Some(bytes) => bytes,
+            None => return,
+        };
+        if let Some(used) = self.storage_used.get_mut(account_id) {
+            *used = used.saturating_sub(bytes);
+            if *used == 0 {
+                self.storage_used.remove(account_id);
+            }
+        }
+    }
+
+    fn flush_orders(&mut self, account_id: &AccountId, order_id: &OrderId) {
+        self.asks.flush();
+        self.public_asks.flush();
+        self.ask_currencies.flush();
+        self.reserved_buyers.flush();
+        self.auctions.flush();
+        self.dutch_auctions.flush();
+        self.bids.flush();
+        self.bid_currencies.flush();
+        self.offers.flush();
+        self.bundles.flush();
+        self.swaps.flush();
+        self.rentals.flush();
+        if let Some(keys) = self.asks_by_owner.get_mut(account_id) {
+            keys.flush();
+        }
+        if let Some(keys) = self.bids_by_account.get_mut(account_id) {
+            keys.flush();
+        }
+        if let Some(ids) = self.offers_by_account.get_mut(account_id) {
+            ids.flush();
+        }
+        if let Some(ids) = self.bundles_by_owner.get_mut(account_id) {
+            ids.flush();
+        }
+        if let Some(ids) = self.swaps_by_account.get_mut(account_id) {
+            ids.flush();
+        }
+        if let Some(keys) = self.rentals_by_owner.get_mut(account_id) {
+            keys.flush();
+        }
+        if let OrderId::Ask((nft_contract_id, _)) | OrderId::Bid((nft_contract_id, _), _) = order_id
+        {
+            if let Some(token_ids) = self.tokens_by_collection.get_mut(nft_contract_id) {
+                token_ids.flush();
+            }
+        }
+        self.asks_by_owner.flush();
+        self.bids_by_account.flush();
+        self.offers_by_account.flush();
+        self.bundles_by_owner.flush();
+        self.swaps_by_account.flush();
+        self.rentals_by_owner.flush();
+        self.tokens_by_collection.flush();
+        self.order_storage.flush();
+        self.storage_used.flush();
+    }
+
+    /// Balance taken by the storage of the account's orders.
+    pub(crate) fn storage_used_by(&self, account_id: &AccountId) -> Balance {
+        Balance::from(
+            self.storage_used
+                .get(account_id)
+                .copied()
+                .unwrap_or_default(),
+        ) * env::storage_byte_cost()
+    }
+
+    /// Check that the account's deposit covers the storage used by its orders.
+    fn check_storage_used(&self, account_id: &AccountId) -> Result<(), ContractError> {
+        let deposit = self
+            .storage_deposits
+            .get(account_id)
+            .copied()
+            .unwrap_or_default();
+        if self.storage_used_by(account_id) > deposit {
+            return Err(ContractError::StorageError(
+                "Not enough storage deposits to store the order",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Total deposit of the account and the part which isn't used by its orders.
+    fn storage_balance(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        let total = *self.storage_deposits.get(account_id)?;
+        let used = self.storage_used_by(account_id);
+
+        Some(StorageBalance {
+            total: U128(total),
+            available: U128(total.saturating_sub(used)),
+        })
+    }
+
+    /// Cancel all the account's orders and refund their deposits.
+    ///
+    /// Fails for an auction with bids, the highest bid of an auction and a rented token,
+    /// which can't be cancelled by their owners either.
+    fn cancel_orders(&mut self, account_id: &AccountId) -> Result<(), ContractError> {
+        let ask_keys: Vec<ContractAndTokenId> = self
+            .asks_by_owner
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        for key in ask_keys {
+            if self.auctions.contains_key(&key)
+                && self
+                    .highest_bid_than_ask(&key, self.ask_currency(&key))
+                    .is_some()
+            {
+                return Err(ContractError::AuctionError(
+                    "The auction with bids can't be cancelled",
+                ));
+            }
+            self.drop_ask(&key);
+        }
+
+        let bid_keys: Vec<(ContractAndTokenId, String)> = self
+            .bids_by_account
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        for (key, bid_id) in bid_keys {
+            if self.auctions.contains_key(&key)
+                && matches!(
+                    self.highest_bid_than_ask(&key, self.ask_currency(&key)),
+                    Some(top_bid) if top_bid.id == bid_id
+                )
+            {
+                return Err(ContractError::AuctionError(
+                    "The highest bid can't be cancelled during the auction",
+                ));
+            }
+            let ft_contract_id = self.bid_currency(&key, &bid_id).cloned();
+            if let Some(bid) = self.remove_bid(&key, &bid_id) {
+                self.refund(bid.account_id(), bid.price(), ft_contract_id.as_ref());
+                emit_event(MarketEvent::RemoveBid(BidEvent::new(
+                    &key.0,
+                    bid,
+                    ft_contract_id.as_ref(),
+                )));
+            }
+        }
+
+        let offer_ids: Vec<u64> = self
+            .offers_by_account
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        for id in offer_ids {
+            if let Some(offer) = self.remove_offer(id) {
+                self.refund(&offer.account_id, offer.price.0, None);
+                emit_event(MarketEvent::RemoveOffer(offer));
+            }
+        }
+
+        let bundle_ids: Vec<u64> = self
+            .bundles_by_owner
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        for id in bundle_ids {
+            if let Some(bundle) = self.remove_bundle(id) {
+                emit_event(MarketEvent::RemoveBundle(bundle));
+            }
+        }
+
+        let swap_ids: Vec<u64> = self
+            .swaps_by_account
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        for id in swap_ids {
+            if let Some(swap) = self.remove_swap(id) {
+                if swap.price.0 > 0 {
+                    self.refund(&swap.maker_id, swap.price.0, None);
+                }
+                emit_event(MarketEvent::RemoveSwap(swap));
+            }
+        }
+
+        let rental_keys: Vec<ContractAndTokenId> = self
+            .rentals_by_owner
+            .get(account_id)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        for key in rental_keys {
+            self.end_lease(&key);
+            if self.rentals.get(&key).map_or(false, Rental::is_leased) {
+                return Err(ContractError::RentalError("The token is rented"));
+            }
+            if let Some(rental) = self.remove_rental(&key) {
+                emit_event(MarketEvent::RemoveRental(rental));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Deposit Nears for the storage of the account's orders, according to NEP-145.
+    ///
+    /// An account registers with at least `storage_balance_bounds().min`.
+    /// With `registration_only` only the minimum is kept and the rest is refunded,
+    /// a registered account gets the whole deposit back.
+    #[payable]
+    #[handle_result]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> Result<StorageBalance, ContractError> {
+        let storage_account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let deposit = env::attached_deposit();
+        let is_registered = self.storage_deposits.contains_key(&storage_account_id);
+        if !is_registered {
+            check_deposit(deposit)?;
+        }
+
+        let refund = match (registration_only, is_registered) {
+            (Some(true), true) => deposit,
+            (Some(true), false) => deposit - STORAGE_PER_SALE,
+            _ => 0,
+        };
+        self.storage_deposits
+            .entry(storage_account_id.clone())
+            .or_default()
+            .add_assign(deposit - refund);
+        if deposit > refund {
+            emit_event(MarketEvent::StorageDeposit(StorageChange {
+                account_id: storage_account_id.clone(),
+                amount: U128(deposit - refund),
+            }));
+        }
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        Ok(self.storage_balance(&storage_account_id).unwrap())
+    }
+
+    /// Withdraw `amount` of the deposit not used by the account's orders, everything available by default.
+    #[payable]
+    #[handle_result]
+    pub fn storage_withdraw(
+        &mut self,
+        amount: Option<U128>,
+    ) -> Result<StorageBalance, ContractError> {
+        check_one_yocto()?;
+        let owner_id = env::predecessor_account_id();
+        let balance = self
+            .storage_balance(&owner_id)
+            .ok_or(ContractError::StorageError("The account isn't registered"))?;
+
+        let amount = amount.unwrap_or(balance.available).0;
+        if amount > balance.available.0 {
+            return Err(ContractError::StorageError(
+                "The amount exceeds the available storage balance",
+            ));
+        }
+        if amount > 0 {
+            self.storage_deposits
+                .insert(owner_id.clone(), balance.total.0 - amount);
+            Promise::new(owner_id.clone()).transfer(amount);
+            emit_event(MarketEvent::StorageWithdraw(StorageChange {
+                account_id: owner_id.clone(),
+                amount: U128(amount),
+            }));
+        }
+
+        Ok(self.storage_balance(&owner_id).unwrap())
+    }
+
+    /// Unregister the account and return its storage deposit.
+    ///
+    /// The account's orders must be cancelled first, or with `force` they're cancelled
+    /// and their deposits are refunded. Returns `false` if the account isn't registered.
+    #[payable]
+    #[handle_result]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> Result<bool, ContractError> {
+        check_one_yocto()?;
+        let owner_id = env::predecessor_account_id();
+        if !self.storage_deposits.contains_key(&owner_id) {
+            return Ok(false);
+        }
+        // Expired bids aren't counted as orders, but they still take the account's storage.
+        self.remove_expired_bids_of(&owner_id);
+        if self.total_orders_by_id(&owner_id) > 0 {
+            if force != Some(true) {
+                return Err(ContractError::StorageError(
+                    "The account has orders, they must be cancelled before unregistering",
+                ));
+            }
+            self.cancel_orders(&owner_id)?;
+        }
+
+        let deposit = self.storage_deposits.remove(&owner_id).unwrap_or_default();
+        if deposit > 0 {
+            Promise::new(owner_id.clone()).transfer(deposit);
+        }
+        emit_event(MarketEvent::StorageUnregister(StorageChange {
+            account_id: owner_id,
+            amount: U128(deposit),
+        }));
+
+        Ok(true)
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128(STORAGE_PER_SALE),
+            max: None,
+        }
+    }
+
+    pub fn storage_minimum_balance(&self) -> U128 {
+        U128(STORAGE_PER_SALE)
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_balance(&account_id)
+    }
+
+    /// The parts of the account's storage deposit used and not used by its orders,
+    /// `None` for an unregistered account.
+    pub fn storage_usage_of(&self, account_id: AccountId) -> Option<StorageUsage> {
+        let balance = self.storage_balance(&account_id)?;
+
+        Some(StorageUsage {
+            used: U128(balance.total.0 - balance.available.0),
+            available: balance.available,
+        })
+    }
+}
+
+fn check_deposit(deposit: Balance) -> Result<(), ContractError> {
+    if deposit < STORAGE_PER_SALE {
+        return Err(ContractError::InsufficientDeposit);
+    }
+
+    Ok(())
+}
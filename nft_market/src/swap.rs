@@ -0,0 +1,407 @@
+use crate::consts::{
+    AFTER_ESCROW_GAS, AFTER_NFT_TOKEN_GAS, ESCROW_TRANSFER_GAS, NFT_TOKEN_GAS, RELEASE_GAS,
+};
+use crate::escrow::escrow;
+use crate::events::{emit_event, MarketEvent};
+use crate::external::nft;
+use crate::helpers::paginate;
+use crate::storage::OrderId;
+use crate::trade::check_prepaid_gas;
+use crate::{Contract, ContractError, ContractExt, StorageKey};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::store::UnorderedSet;
+use near_sdk::{env, near_bindgen, AccountId, Balance, Gas};
+
+/// Barter order: the maker's token plus `price` in Nears for the wanted token plus `wanted_price` in Nears.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Swap {
+    pub id: u64,
+    pub maker_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub approval_id: u64,
+    pub wanted_nft_contract_id: AccountId,
+    pub wanted_token_id: TokenId,
+    /// Nears which the maker adds to the token.
+    pub price: U128,
+    /// Nears which the taker adds to the wanted token.
+    pub wanted_price: U128,
+}
+
+/// Gas of `on_swap`: both tokens go to their new owners or back.
+fn on_swap_gas() -> Gas {
+    AFTER_ESCROW_GAS + RELEASE_GAS * 2
+}
+
+/// Gas of the calls `exchange_swap` makes: both tokens are moved to the market, then `on_swap`.
+pub(crate) fn exchange_swap_gas() -> Gas {
+    ESCROW_TRANSFER_GAS * 2 + on_swap_gas()
+}
+
+/// Gas of `on_accept_swap`, which exchanges the tokens.
+fn on_accept_swap_gas() -> Gas {
+    AFTER_NFT_TOKEN_GAS + exchange_swap_gas()
+}
+
+impl Contract {
+    pub(crate) fn count_swaps_for_account(&self, account_id: &AccountId) -> usize {
+        self.swaps_by_account
+            .get(account_id)
+            .map_or(0, |ids| ids.len() as usize)
+    }
+
+    /// Store the swap and index it by the maker.
+    pub(crate) fn insert_swap(&mut self, swap: Swap) -> Result<(), ContractError> {
+        let account_id = swap.maker_id.clone();
+        self.charge_storage(&account_id, OrderId::Swap(swap.id), |this| {
+            this.swaps_by_account
+                .entry(swap.maker_id.clone())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::SwapsByAccountInner {
+                        account_hash: env::sha256(swap.maker_id.as_bytes()),
+                    })
+                })
+                .insert(swap.id);
+            this.swaps.insert(swap.id, swap);
+        })
+    }
+
+    pub(crate) fn remove_swap(&mut self, id: u64) -> Option<Swap> {
+        let swap = self.swaps.remove(&id)?;
+        self.release_storage(&swap.maker_id, &OrderId::Swap(id));
+        if let Some(ids) = self.swaps_by_account.get_mut(&swap.maker_id) {
+            ids.remove(&id);
+            ids.is_empty()
+                .then(|| self.swaps_by_account.remove(&swap.maker_id));
+        }
+
+        Some(swap)
+    }
+
+    /// Exchange the tokens of the swap, the taker has approved the market for the wanted token
+    /// and pays `payment` Nears for the maker's token.
+    ///
+    /// Both tokens are moved to the market first, so the swap is unwound if either of them can't be moved.
+    pub(crate) fn exchange_swap(
+        &mut self,
+        nft_contract_id: AccountId,
+        token_id: TokenId,
+        taker_id: AccountId,
+        approval_id: u64,
+        swap_id: u64,
+        payment: Balance,
+    ) -> Result<(), ContractError> {
+        let swap = self
+            .swaps
+            .get(&swap_id)
+            .ok_or(ContractError::NotFound("Swap doesn't exist"))?;
+        if swap.wanted_nft_contract_id != nft_contract_id || swap.wanted_token_id != token_id {
+            return Err(ContractError::SwapError(
+                "The swap is made for another token",
+            ));
+        }
+        if swap.maker_id == taker_id {
+            return Err(ContractError::SwapError(
+                "The swap can't be accepted by its maker",
+            ));
+        }
+        if swap.wanted_price.0 != payment {
+            return Err(ContractError::SwapError(
+                "The swap's wanted price must be paid by `accept_swap`",
+            ));
+        }
+
+        let swap = self.remove_swap(swap_id).unwrap();
+        let tokens = [
+            (
+                swap.nft_contract_id.clone(),
+                swap.token_id.clone(),
+                swap.approval_id,
+            ),
+            (nft_contract_id, token_id, approval_id),
+        ];
+        // The callback moves both tokens once more, to the new owners or back.
+        escrow(&tokens).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(on_swap_gas())
+                .on_swap(swap, taker_id),
+        );
+
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Offer the caller's token and the attached deposit for the wanted token and `wanted_price` Nears.
+    ///
+    /// The caller must have approved the market for the token with `approval_id`,
+    /// the swap appears in the market after `nft_token` confirms that the caller owns the token.
+    /// The owner of the wanted token accepts the swap by `nft_approve` with `{"accept_swap": {"swap_id": 1}}` message,
+    /// or by `accept_swap` when the swap has a wanted price.
+    /// Only one side of the swap can add Nears.
+    /// Returns the swap's id.
+    #[payable]
+    #[handle_result]
+    pub fn add_swap(
+        &mut self,
+        token_id: TokenId,
+        approval_id: u64,
+        wanted_token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        wanted_nft_contract_id: Option<AccountId>,
+        wanted_price: Option<U128>,
+    ) -> Result<u64, ContractError> {
+        let nft_contract_id = nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
+        let wanted_nft_contract_id = wanted_nft_contract_id.unwrap_or_else(|| self.nft_id.clone());
+        self.check_nft_contract(&nft_contract_id)?;
+        self.check_nft_contract(&wanted_nft_contract_id)?;
+        self.check_not_paused()?;
+        if nft_contract_id == wanted_nft_contract_id && token_id == wanted_token_id {
+            return Err(ContractError::SwapError(
+                "The token can't be swapped for itself",
+            ));
+        }
+        let wanted_price = wanted_price.unwrap_or(U128(0));
+        if env::attached_deposit() > 0 && wanted_price.0 > 0 {
+            return Err(ContractError::SwapError(
+                "Only one side of the swap can add Nears",
+            ));
+        }
+        let maker_id = env::predecessor_account_id();
+        self.check_storage_deposits(&maker_id)?;
+        self.check_orders_per_account(&maker_id)?;
+
+        let swap = Swap {
+            id: self.next_swap_id,
+            maker_id,
+            nft_contract_id,
+            token_id,
+            approval_id,
+            wanted_nft_contract_id,
+            wanted_token_id,
+            price: U128(env::attached_deposit()),
+            wanted_price,
+        };
+        self.next_swap_id += 1;
+
+        let id = swap.id;
+        nft::ext(swap.nft_contract_id.clone())
+            .with_static_gas(NFT_TOKEN_GAS)
+            .nft_token(swap.token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(AFTER_NFT_TOKEN_GAS)
+                    .on_add_swap(swap),
+            );
+
+        Ok(id)
+    }
+
+    /// Accept the swap with the attached deposit, which must be equal to the swap's wanted price.
+    ///
+    /// The caller must have approved the market for the wanted token with `approval_id`,
+    /// the tokens are exchanged after `nft_token` confirms that the caller owns the wanted token.
+    #[payable]
+    #[handle_result]
+    pub fn accept_swap(&mut self, swap_id: u64, approval_id: u64) -> Result<(), ContractError> {
+        self.check_not_paused()?;
+        let swap = self
+            .swaps
+            .get(&swap_id)
+            .ok_or(ContractError::NotFound("Swap doesn't exist"))?;
+        let payment = env::attached_deposit();
+        if swap.wanted_price.0 != payment {
+            return Err(ContractError::SwapError(
+                "The attached deposit must be equal to the swap's wanted price",
+            ));
+        }
+        check_prepaid_gas(NFT_TOKEN_GAS + on_accept_swap_gas())?;
+
+        nft::ext(swap.wanted_nft_contract_id.clone())
+            .with_static_gas(NFT_TOKEN_GAS)
+            .nft_token(swap.wanted_token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(on_accept_swap_gas())
+                    .on_accept_swap(
+                        swap_id,
+                        env::predecessor_account_id(),
+                        approval_id,
+                        U128(payment),
+                    ),
+            );
+
+        Ok(())
+    }
+
+    #[handle_result]
+    pub fn cancel_swap(&mut self, swap_id: u64) -> Result<(), ContractError> {
+        let swap = self
+            .swaps
+            .get(&swap_id)
+            .ok_or(ContractError::NotFound("Swap doesn't exist"))?;
+        if swap.maker_id != env::predecessor_account_id() {
+            return Err(ContractError::NotAuthorized(
+                "Swap's maker is not the same as the caller",
+            ));
+        }
+
+        let swap = self.remove_swap(swap_id).unwrap();
+        if swap.price.0 > 0 {
+            self.refund(&swap.maker_id, swap.price.0, None);
+        }
+        emit_event(MarketEvent::RemoveSwap(swap));
+
+        Ok(())
+    }
+
+    pub fn swap(&self, swap_id: u64) -> Option<&Swap> {
+        self.swaps.get(&swap_id)
+    }
+
+    pub fn swaps(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<&Swap> {
+        paginate(self.swaps.values(), from_index, limit)
+    }
+
+    pub fn swaps_by_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&Swap> {
+        let ids = self.swaps_by_account.get(&account_id).into_iter().flatten();
+        paginate(ids, from_index, limit)
+            .into_iter()
+            .flat_map(|id| self.swaps.get(id))
+            .collect()
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{setup, DEPOSIT};
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    fn swap(maker_id: AccountId) -> Swap {
+        Swap {
+            id: 0,
+            maker_id,
+            nft_contract_id: accounts(0),
+            token_id: "1".to_string(),
+            approval_id: 1,
+            wanted_nft_contract_id: accounts(0),
+            wanted_token_id: "2".to_string(),
+            price: U128(0),
+            wanted_price: U128(0),
+        }
+    }
+
+    #[test]
+    fn swap_is_accepted_only_for_wanted_token() {
+        let (_, mut contract) = setup(accounts(2), DEPOSIT);
+        contract.insert_swap(swap(accounts(2))).unwrap();
+        assert_eq!(contract.count_swaps_for_account(&accounts(2)), 1);
+
+        assert!(matches!(
+            contract.exchange_swap(accounts(0), "3".to_string(), accounts(3), 1, 0, 0),
+            Err(ContractError::SwapError(_))
+        ));
+        assert!(matches!(
+            contract.exchange_swap(accounts(0), "2".to_string(), accounts(2), 1, 0, 0),
+            Err(ContractError::SwapError(_))
+        ));
+        assert!(matches!(
+            contract.exchange_swap(accounts(0), "2".to_string(), accounts(3), 1, 1, 0),
+            Err(ContractError::NotFound(_))
+        ));
+
+        contract
+            .exchange_swap(accounts(0), "2".to_string(), accounts(3), 1, 0, 0)
+            .unwrap();
+        assert!(contract.swap(0).is_none());
+        assert!(contract
+            .swaps_by_account(accounts(2), None, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn swap_accepted_by_approval_needs_gas_for_exchange() {
+        let (mut context, mut contract) = setup(accounts(2), DEPOSIT);
+        contract.insert_swap(swap(accounts(2))).unwrap();
+        let msg = r#"{"accept_swap":{"swap_id":0}}"#.to_string();
+
+        context
+            .predecessor_account_id(accounts(0))
+            .prepaid_gas(Gas(50_000_000_000_000));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.nft_on_approve("2".to_string(), accounts(3), 1, msg.clone()),
+            Err(ContractError::NotEnoughGas(_))
+        ));
+
+        context.prepaid_gas(Gas(300_000_000_000_000));
+        testing_env!(context.build());
+        contract
+            .nft_on_approve("2".to_string(), accounts(3), 1, msg)
+            .unwrap();
+        assert!(contract.swap(0).is_none());
+    }
+
+    #[test]
+    fn swap_is_accepted_only_with_wanted_price() {
+        let (mut context, mut contract) = setup(accounts(2), DEPOSIT);
+        contract
+            .insert_swap(Swap {
+                wanted_price: U128(100),
+                ..swap(accounts(2))
+            })
+            .unwrap();
+
+        assert!(matches!(
+            contract.exchange_swap(accounts(0), "2".to_string(), accounts(3), 1, 0, 0),
+            Err(ContractError::SwapError(_))
+        ));
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(99)
+            .prepaid_gas(Gas(300_000_000_000_000));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.accept_swap(0, 1),
+            Err(ContractError::SwapError(_))
+        ));
+
+        context.attached_deposit(100);
+        testing_env!(context.build());
+        contract.accept_swap(0, 1).unwrap();
+        // The swap is exchanged after the owner of the wanted token is confirmed.
+        assert!(contract.swap(0).is_some());
+    }
+
+    #[test]
+    fn swap_is_cancelled_only_by_maker() {
+        let (mut context, mut contract) = setup(accounts(2), DEPOSIT);
+        contract.insert_swap(swap(accounts(2))).unwrap();
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.cancel_swap(0),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.cancel_swap(0).unwrap();
+        assert!(contract.swaps(None, None).is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::consts::STORAGE_PER_SALE;
+use crate::xcc::NonFungibleTokenApprovalReceiver;
+use crate::Contract;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, AccountId, Balance};
+
+/// Storage deposit enough for a single order.
+pub(crate) const DEPOSIT: Balance = STORAGE_PER_SALE;
+
+/// The context of a call from `predecessor_account_id` to the market at `accounts(0)`.
+pub(crate) fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(accounts(0))
+        .signer_account_id(predecessor_account_id.clone())
+        .predecessor_account_id(predecessor_account_id);
+    builder
+}
+
+/// The market owned by `accounts(1)` with `accounts(0)` as the NFT contract,
+/// `account_id` has deposited `deposit` for storage.
+///
+/// The context is left as a call from `accounts(1)` without a deposit.
+pub(crate) fn setup(account_id: AccountId, deposit: Balance) -> (VMContextBuilder, Contract) {
+    let mut context = get_context(accounts(1));
+    context.attached_deposit(deposit);
+    testing_env!(context.build());
+    let mut contract = Contract::init(accounts(0));
+    contract.storage_deposit(Some(account_id), None).unwrap();
+
+    context.attached_deposit(0);
+    testing_env!(context.build());
+    (context, contract)
+}
+
+/// Alice, `accounts(2)`, approves the market for token "1" with approval 1 and `msg`.
+///
+/// The context is left as a call from the NFT contract.
+pub(crate) fn list_token(msg: &str) -> (VMContextBuilder, Contract) {
+    let (mut context, mut contract) = setup(accounts(2), DEPOSIT);
+    context.predecessor_account_id(accounts(0));
+    testing_env!(context.build());
+    contract
+        .nft_on_approve("1".to_string(), accounts(2), 1, msg.to_string())
+        .unwrap();
+
+    (context, contract)
+}
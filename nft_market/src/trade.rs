@@ -1,7 +1,49 @@
+use crate::consts::{
+    AFTER_NFT_TRANSFER_GAS, AFTER_REFUND_GAS, FT_TRANSFER_GAS, MAX_LEN_PAYOUT, NEAR_TRANSFER_GAS,
+    NFT_TRANSFER_GAS, PAYMENTS_PER_TRADE, PLACE_ORDER_GAS,
+};
 use crate::external::*;
+use crate::ContractError;
 use battlemon_models::market::ask::AskForContract;
 use battlemon_models::market::bid::BidForContract;
-use near_sdk::env;
+use near_sdk::json_types::U128;
+use near_sdk::{env, AccountId, Gas};
+
+/// Gas of `on_trade` with its payments.
+///
+/// Fungible tokens are paid with a separate `ft_transfer` call to
+/// every payout receiver, the bidder and the treasury, each with the callback
+/// which credits a failed payment. A failed trade refunds the bidder instead, which takes less.
+pub(crate) fn on_trade_gas(ft_contract_id: Option<&AccountId>) -> Gas {
+    let payment_gas = match ft_contract_id {
+        None => NEAR_TRANSFER_GAS,
+        Some(_) => FT_TRANSFER_GAS + AFTER_REFUND_GAS,
+    };
+
+    AFTER_NFT_TRANSFER_GAS + payment_gas * PAYMENTS_PER_TRADE
+}
+
+/// Gas the trade's calls take: `nft_transfer_payout` and `on_trade`.
+pub(crate) fn trade_gas(ft_contract_id: Option<&AccountId>) -> Gas {
+    NFT_TRANSFER_GAS + on_trade_gas(ft_contract_id)
+}
+
+/// Check that the method is prepaid with `PLACE_ORDER_GAS` and the gas of the calls it makes.
+///
+/// It's checked before the order is matched, a trade started without enough
+/// gas would fail midway and leave the bidder's deposit in the market.
+pub(crate) fn check_prepaid_gas(calls_gas: Gas) -> Result<(), ContractError> {
+    let required = PLACE_ORDER_GAS + calls_gas;
+    if env::prepaid_gas() < required {
+        return Err(ContractError::NotEnoughGas(ceil_tgas(required)));
+    }
+
+    Ok(())
+}
+
+fn ceil_tgas(gas: Gas) -> u64 {
+    (gas.0 + Gas::ONE_TERA.0 - 1) / Gas::ONE_TERA.0
+}
 
 impl crate::Contract {
     /// * asker - wants near for token
@@ -23,20 +65,128 @@ impl crate::Contract {
     ///
     /// *Result:* Asker must receive a bidder's 1 Near,
     /// and the bidder accepts the asker's token and 1 Near for change.
-    pub(crate) fn trade(&mut self, ask: AskForContract, bid: BidForContract, change: bool) {
-        nft::ext(self.nft_id.clone())
+    ///
+    /// The market fee is taken from the trade price,
+    /// the rest is split according to the NFT contract's payout.
+    /// Both orders are in Nears or in the same fungible token `ft_contract_id`.
+    pub(crate) fn trade(
+        &mut self,
+        nft_contract_id: AccountId,
+        ask: AskForContract,
+        bid: BidForContract,
+        change: bool,
+        ft_contract_id: Option<AccountId>,
+    ) {
+        let price = if change { ask.price() } else { bid.price() };
+        let market_fee = self.fee_config.calculate(price);
+        let on_trade_gas = on_trade_gas(ft_contract_id.as_ref());
+
+        nft::ext(nft_contract_id.clone())
             .with_attached_deposit(1)
-            .with_static_gas(10_000_000_000_000.into())
-            .nft_transfer(
+            .with_static_gas(NFT_TRANSFER_GAS)
+            .nft_transfer_payout(
                 bid.account_id().to_owned(),
                 bid.token_id().to_owned(),
                 ask.approval_id(),
                 None,
+                U128(price - market_fee),
+                Some(MAX_LEN_PAYOUT),
             )
             .then(
                 Self::ext(env::current_account_id())
-                    .with_static_gas(10_000_000_000_000.into())
-                    .on_trade(ask, bid, change),
+                    .with_static_gas(on_trade_gas)
+                    .on_trade(
+                        nft_contract_id,
+                        ask,
+                        bid,
+                        change,
+                        U128(market_fee),
+                        ft_contract_id,
+                    ),
             );
     }
 }
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::consts::{BUY_METHOD_TOTAL_GAS, STORAGE_PER_SALE};
+    use crate::test_utils::get_context;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use crate::Contract;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn buy_method_total_gas_covers_trade_for_nears() {
+        assert_eq!(BUY_METHOD_TOTAL_GAS, PLACE_ORDER_GAS + trade_gas(None));
+    }
+
+    #[test]
+    fn on_trade_gas_covers_every_payment_and_refund() {
+        let ft_contract_id = accounts(3);
+        assert!(trade_gas(Some(&ft_contract_id)) > trade_gas(None));
+        assert_eq!(
+            on_trade_gas(Some(&ft_contract_id)),
+            AFTER_NFT_TRANSFER_GAS + (FT_TRANSFER_GAS + AFTER_REFUND_GAS) * PAYMENTS_PER_TRADE
+        );
+        assert_eq!(
+            on_trade_gas(None),
+            AFTER_NFT_TRANSFER_GAS + NEAR_TRANSFER_GAS * (MAX_LEN_PAYOUT as u64 + 2)
+        );
+    }
+
+    #[test]
+    fn prepaid_gas_is_checked_at_boundary() {
+        let mut context = get_context(accounts(1));
+        context.prepaid_gas(BUY_METHOD_TOTAL_GAS - Gas(1));
+        testing_env!(context.build());
+        assert!(matches!(
+            check_prepaid_gas(trade_gas(None)),
+            Err(ContractError::NotEnoughGas(108))
+        ));
+
+        context.prepaid_gas(BUY_METHOD_TOTAL_GAS);
+        testing_env!(context.build());
+        assert!(check_prepaid_gas(trade_gas(None)).is_ok());
+    }
+
+    #[test]
+    fn bid_matching_ask_fails_without_gas_for_trade() {
+        let mut context = get_context(accounts(2));
+        context.attached_deposit(STORAGE_PER_SALE);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        contract.storage_deposit(None, None).unwrap();
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract
+            .nft_on_approve(
+                "1".to_string(),
+                accounts(2),
+                1,
+                format!("{{\"price\":\"{STORAGE_PER_SALE}\"}}"),
+            )
+            .unwrap();
+
+        context
+            .predecessor_account_id(accounts(1))
+            .prepaid_gas(BUY_METHOD_TOTAL_GAS - Gas(1));
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+        assert!(matches!(
+            contract.add_bid("1".to_string(), None, None),
+            Err(ContractError::NotEnoughGas(_))
+        ));
+        assert!(contract.ask("1".to_string(), None).is_some());
+
+        context.prepaid_gas(BUY_METHOD_TOTAL_GAS);
+        testing_env!(context.build());
+        contract.add_bid("1".to_string(), None, None).unwrap();
+        assert!(contract.ask("1".to_string(), None).is_none());
+        assert!(contract
+            .bids_for_token("1".to_string(), None, None, None)
+            .is_empty());
+    }
+}
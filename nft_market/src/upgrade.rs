@@ -0,0 +1,847 @@
+use crate::bid::cmp_bids;
+use crate::consts::{MAX_TRADE_HISTORY, MIGRATE_GAS, NO_DEPOSIT};
+use crate::events::{emit_event, MarketEvent};
+use crate::history::TradeHistory;
+use crate::storage::OrderId;
+use crate::{
+    Auction, Bundle, Contract, ContractAndTokenId, ContractError, ContractExt, DutchAuction,
+    FeeConfig, Offer, OrderLimits, Rental, StorageKey, Swap,
+};
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::store::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::{env, near_bindgen, AccountId, Balance, FunctionError, Promise};
+
+/// The version is stored apart from the state, so it can be read before the state layout is known.
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+/// Version of the current `Contract` layout.
+pub(crate) const STATE_VERSION: u32 = 5;
+
+/// The `Swap` layout before the taker's Nears.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct SwapV4 {
+    id: u64,
+    maker_id: AccountId,
+    nft_contract_id: AccountId,
+    token_id: TokenId,
+    approval_id: u64,
+    wanted_nft_contract_id: AccountId,
+    wanted_token_id: TokenId,
+    price: U128,
+}
+
+impl From<SwapV4> for Swap {
+    fn from(swap: SwapV4) -> Self {
+        Self {
+            id: swap.id,
+            maker_id: swap.maker_id,
+            nft_contract_id: swap.nft_contract_id,
+            token_id: swap.token_id,
+            approval_id: swap.approval_id,
+            wanted_nft_contract_id: swap.wanted_nft_contract_id,
+            wanted_token_id: swap.wanted_token_id,
+            price: swap.price,
+            wanted_price: U128(0),
+        }
+    }
+}
+
+/// The `Contract` layout before the indexes of public asks and tokens by collection,
+/// claimable tokens and the taker's Nears of swaps.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct ContractV4 {
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    guardian_id: Option<AccountId>,
+    paused: bool,
+    nft_id: AccountId,
+    nft_ids: UnorderedSet<AccountId>,
+    ft_ids: UnorderedSet<AccountId>,
+    fee_config: FeeConfig,
+    order_limits: OrderLimits,
+    asks: UnorderedMap<ContractAndTokenId, AskForContract>,
+    bids: UnorderedMap<ContractAndTokenId, Vec<BidForContract>>,
+    ask_currencies: LookupMap<ContractAndTokenId, AccountId>,
+    reserved_buyers: LookupMap<ContractAndTokenId, Vec<AccountId>>,
+    bid_currencies: LookupMap<(ContractAndTokenId, String), AccountId>,
+    auctions: LookupMap<ContractAndTokenId, Auction>,
+    dutch_auctions: LookupMap<ContractAndTokenId, DutchAuction>,
+    offers: UnorderedMap<u64, Offer>,
+    offers_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_offer_id: u64,
+    bundles: UnorderedMap<u64, Bundle>,
+    bundles_by_owner: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_bundle_id: u64,
+    swaps: UnorderedMap<u64, SwapV4>,
+    swaps_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_swap_id: u64,
+    rentals: UnorderedMap<ContractAndTokenId, Rental>,
+    rentals_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    asks_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    bids_by_account: LookupMap<AccountId, UnorderedSet<(ContractAndTokenId, String)>>,
+    storage_deposits: LookupMap<AccountId, Balance>,
+    storage_used: LookupMap<AccountId, u64>,
+    order_storage: LookupMap<OrderId, u64>,
+    claimable_balances: LookupMap<AccountId, Balance>,
+    claimable_ft_balances: LookupMap<(AccountId, AccountId), Balance>,
+    trade_history: TradeHistory,
+}
+
+/// The `Contract` layout before order limits.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct ContractV3 {
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    guardian_id: Option<AccountId>,
+    paused: bool,
+    nft_id: AccountId,
+    nft_ids: UnorderedSet<AccountId>,
+    ft_ids: UnorderedSet<AccountId>,
+    fee_config: FeeConfig,
+    asks: UnorderedMap<ContractAndTokenId, AskForContract>,
+    bids: UnorderedMap<ContractAndTokenId, Vec<BidForContract>>,
+    ask_currencies: LookupMap<ContractAndTokenId, AccountId>,
+    reserved_buyers: LookupMap<ContractAndTokenId, Vec<AccountId>>,
+    bid_currencies: LookupMap<(ContractAndTokenId, String), AccountId>,
+    auctions: LookupMap<ContractAndTokenId, Auction>,
+    dutch_auctions: LookupMap<ContractAndTokenId, DutchAuction>,
+    offers: UnorderedMap<u64, Offer>,
+    offers_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_offer_id: u64,
+    bundles: UnorderedMap<u64, Bundle>,
+    bundles_by_owner: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_bundle_id: u64,
+    swaps: UnorderedMap<u64, SwapV4>,
+    swaps_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_swap_id: u64,
+    rentals: UnorderedMap<ContractAndTokenId, Rental>,
+    rentals_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    asks_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    bids_by_account: LookupMap<AccountId, UnorderedSet<(ContractAndTokenId, String)>>,
+    storage_deposits: LookupMap<AccountId, Balance>,
+    storage_used: LookupMap<AccountId, u64>,
+    order_storage: LookupMap<OrderId, u64>,
+    claimable_balances: LookupMap<AccountId, Balance>,
+    claimable_ft_balances: LookupMap<(AccountId, AccountId), Balance>,
+    trade_history: TradeHistory,
+}
+
+/// The `Contract` layout before rentals.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct ContractV2 {
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    guardian_id: Option<AccountId>,
+    paused: bool,
+    nft_id: AccountId,
+    nft_ids: UnorderedSet<AccountId>,
+    ft_ids: UnorderedSet<AccountId>,
+    fee_config: FeeConfig,
+    asks: UnorderedMap<ContractAndTokenId, AskForContract>,
+    bids: UnorderedMap<ContractAndTokenId, Vec<BidForContract>>,
+    ask_currencies: LookupMap<ContractAndTokenId, AccountId>,
+    reserved_buyers: LookupMap<ContractAndTokenId, Vec<AccountId>>,
+    bid_currencies: LookupMap<(ContractAndTokenId, String), AccountId>,
+    auctions: LookupMap<ContractAndTokenId, Auction>,
+    dutch_auctions: LookupMap<ContractAndTokenId, DutchAuction>,
+    offers: UnorderedMap<u64, Offer>,
+    offers_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_offer_id: u64,
+    bundles: UnorderedMap<u64, Bundle>,
+    bundles_by_owner: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_bundle_id: u64,
+    swaps: UnorderedMap<u64, SwapV4>,
+    swaps_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    next_swap_id: u64,
+    asks_by_owner: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    bids_by_account: LookupMap<AccountId, UnorderedSet<(ContractAndTokenId, String)>>,
+    storage_deposits: LookupMap<AccountId, Balance>,
+    storage_used: LookupMap<AccountId, u64>,
+    order_storage: LookupMap<OrderId, u64>,
+    claimable_balances: LookupMap<AccountId, Balance>,
+    claimable_ft_balances: LookupMap<(AccountId, AccountId), Balance>,
+    trade_history: TradeHistory,
+}
+
+/// The `Contract` layout of the deployments made before versioning.
+///
+/// The market had no owner and kept the orders for the tokens of one NFT contract.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct ContractV0 {
+    nft_id: AccountId,
+    asks: UnorderedMap<TokenId, AskForContract>,
+    bids: UnorderedMap<TokenId, Vec<BidForContract>>,
+    storage_deposits: LookupMap<AccountId, Balance>,
+}
+
+impl ContractV2 {
+    /// Key the orders by the NFT contract as well and index them by their accounts,
+    /// the caller of `migrate` becomes the owner.
+    ///
+    /// The orders were placed before storage charges, so they aren't charged.
+    fn from_v0(contract: ContractV0, owner_id: AccountId) -> Self {
+        let ContractV0 {
+            nft_id,
+            asks: mut old_asks,
+            bids: mut old_bids,
+            storage_deposits,
+        } = contract;
+        let token_ids: Vec<TokenId> = old_asks.keys().cloned().collect();
+        let asks_v0: Vec<(TokenId, AskForContract)> = token_ids
+            .into_iter()
+            .flat_map(|token_id| Some((token_id.clone(), old_asks.remove(&token_id)?)))
+            .collect();
+        let token_ids: Vec<TokenId> = old_bids.keys().cloned().collect();
+        let bids_v0: Vec<(TokenId, Vec<BidForContract>)> = token_ids
+            .into_iter()
+            .flat_map(|token_id| Some((token_id.clone(), old_bids.remove(&token_id)?)))
+            .collect();
+        // The old entries must be removed from the storage before the new ones are written.
+        old_asks.flush();
+        old_bids.flush();
+
+        let mut asks = UnorderedMap::new(StorageKey::Asks);
+        let mut asks_by_owner = LookupMap::new(StorageKey::AsksByOwner);
+        for (token_id, ask) in asks_v0 {
+            let key = (nft_id.clone(), token_id);
+            asks_by_owner
+                .entry(ask.account_id().to_owned())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::AsksByOwnerInner {
+                        account_hash: env::sha256(ask.account_id().as_bytes()),
+                    })
+                })
+                .insert(key.clone());
+            asks.insert(key, ask);
+        }
+
+        let mut bids = UnorderedMap::new(StorageKey::Bids);
+        let mut bids_by_account = LookupMap::new(StorageKey::BidsByAccount);
+        for (token_id, mut token_bids) in bids_v0 {
+            let key = (nft_id.clone(), token_id);
+            token_bids.sort_by(cmp_bids);
+            for bid in token_bids.iter() {
+                bids_by_account
+                    .entry(bid.account_id().to_owned())
+                    .or_insert_with(|| {
+                        UnorderedSet::new(StorageKey::BidsByAccountInner {
+                            account_hash: env::sha256(bid.account_id().as_bytes()),
+                        })
+                    })
+                    .insert((key.clone(), bid.id.clone()));
+            }
+            bids.insert(key, token_bids);
+        }
+
+        let mut nft_ids = UnorderedSet::new(StorageKey::NftIds);
+        nft_ids.insert(nft_id.clone());
+
+        Self {
+            fee_config: FeeConfig {
+                fee: 0,
+                treasury_id: owner_id.clone(),
+            },
+            owner_id,
+            pending_owner_id: None,
+            guardian_id: None,
+            paused: false,
+            nft_id,
+            nft_ids,
+            ft_ids: UnorderedSet::new(StorageKey::FtIds),
+            asks,
+            bids,
+            ask_currencies: LookupMap::new(StorageKey::AskCurrencies),
+            reserved_buyers: LookupMap::new(StorageKey::ReservedBuyers),
+            bid_currencies: LookupMap::new(StorageKey::BidCurrencies),
+            auctions: LookupMap::new(StorageKey::Auctions),
+            dutch_auctions: LookupMap::new(StorageKey::DutchAuctions),
+            offers: UnorderedMap::new(StorageKey::Offers),
+            offers_by_account: LookupMap::new(StorageKey::OffersByAccount),
+            next_offer_id: 0,
+            bundles: UnorderedMap::new(StorageKey::Bundles),
+            bundles_by_owner: LookupMap::new(StorageKey::BundlesByOwner),
+            next_bundle_id: 0,
+            swaps: UnorderedMap::new(StorageKey::Swaps),
+            swaps_by_account: LookupMap::new(StorageKey::SwapsByAccount),
+            next_swap_id: 0,
+            asks_by_owner,
+            bids_by_account,
+            storage_deposits,
+            storage_used: LookupMap::new(StorageKey::StorageUsed),
+            order_storage: LookupMap::new(StorageKey::OrderStorage),
+            claimable_balances: LookupMap::new(StorageKey::ClaimableBalances),
+            claimable_ft_balances: LookupMap::new(StorageKey::ClaimableFtBalances),
+            trade_history: TradeHistory::new(MAX_TRADE_HISTORY),
+        }
+    }
+}
+
+impl From<ContractV2> for ContractV3 {
+    fn from(contract: ContractV2) -> Self {
+        Self {
+            owner_id: contract.owner_id,
+            pending_owner_id: contract.pending_owner_id,
+            guardian_id: contract.guardian_id,
+            paused: contract.paused,
+            nft_id: contract.nft_id,
+            nft_ids: contract.nft_ids,
+            ft_ids: contract.ft_ids,
+            fee_config: contract.fee_config,
+            asks: contract.asks,
+            bids: contract.bids,
+            ask_currencies: contract.ask_currencies,
+            reserved_buyers: contract.reserved_buyers,
+            bid_currencies: contract.bid_currencies,
+            auctions: contract.auctions,
+            dutch_auctions: contract.dutch_auctions,
+            offers: contract.offers,
+            offers_by_account: contract.offers_by_account,
+            next_offer_id: contract.next_offer_id,
+            bundles: contract.bundles,
+            bundles_by_owner: contract.bundles_by_owner,
+            next_bundle_id: contract.next_bundle_id,
+            swaps: contract.swaps,
+            swaps_by_account: contract.swaps_by_account,
+            next_swap_id: contract.next_swap_id,
+            rentals: UnorderedMap::new(StorageKey::Rentals),
+            rentals_by_owner: LookupMap::new(StorageKey::RentalsByOwner),
+            asks_by_owner: contract.asks_by_owner,
+            bids_by_account: contract.bids_by_account,
+            storage_deposits: contract.storage_deposits,
+            storage_used: contract.storage_used,
+            order_storage: contract.order_storage,
+            claimable_balances: contract.claimable_balances,
+            claimable_ft_balances: contract.claimable_ft_balances,
+            trade_history: contract.trade_history,
+        }
+    }
+}
+
+impl From<ContractV3> for ContractV4 {
+    fn from(contract: ContractV3) -> Self {
+        Self {
+            owner_id: contract.owner_id,
+            pending_owner_id: contract.pending_owner_id,
+            guardian_id: contract.guardian_id,
+            paused: contract.paused,
+            nft_id: contract.nft_id,
+            nft_ids: contract.nft_ids,
+            ft_ids: contract.ft_ids,
+            fee_config: contract.fee_config,
+            order_limits: OrderLimits::default(),
+            asks: contract.asks,
+            bids: contract.bids,
+            ask_currencies: contract.ask_currencies,
+            reserved_buyers: contract.reserved_buyers,
+            bid_currencies: contract.bid_currencies,
+            auctions: contract.auctions,
+            dutch_auctions: contract.dutch_auctions,
+            offers: contract.offers,
+            offers_by_account: contract.offers_by_account,
+            next_offer_id: contract.next_offer_id,
+            bundles: contract.bundles,
+            bundles_by_owner: contract.bundles_by_owner,
+            next_bundle_id: contract.next_bundle_id,
+            swaps: contract.swaps,
+            swaps_by_account: contract.swaps_by_account,
+            next_swap_id: contract.next_swap_id,
+            rentals: contract.rentals,
+            rentals_by_owner: contract.rentals_by_owner,
+            asks_by_owner: contract.asks_by_owner,
+            bids_by_account: contract.bids_by_account,
+            storage_deposits: contract.storage_deposits,
+            storage_used: contract.storage_used,
+            order_storage: contract.order_storage,
+            claimable_balances: contract.claimable_balances,
+            claimable_ft_balances: contract.claimable_ft_balances,
+            trade_history: contract.trade_history,
+        }
+    }
+}
+
+impl From<ContractV4> for Contract {
+    fn from(contract: ContractV4) -> Self {
+        // Private asks have reserved buyers, the rest are indexed as public.
+        let mut public_asks = UnorderedSet::new(StorageKey::PublicAsks);
+        for key in contract.asks.keys() {
+            if !contract.reserved_buyers.contains_key(key) {
+                public_asks.insert(key.to_owned());
+            }
+        }
+
+        let mut tokens_by_collection: LookupMap<AccountId, UnorderedSet<TokenId>> =
+            LookupMap::new(StorageKey::TokensByCollection);
+        for (nft_contract_id, token_id) in contract.asks.keys().chain(contract.bids.keys()) {
+            tokens_by_collection
+                .entry(nft_contract_id.to_owned())
+                .or_insert_with(|| {
+                    UnorderedSet::new(StorageKey::TokensByCollectionInner {
+                        account_hash: env::sha256(nft_contract_id.as_bytes()),
+                    })
+                })
+                .insert(token_id.to_owned());
+        }
+
+        // The swaps are stored again in the new layout under the same prefix.
+        let mut old_swaps = contract.swaps;
+        let ids: Vec<u64> = old_swaps.keys().copied().collect();
+        let swaps_v4: Vec<SwapV4> = ids.iter().flat_map(|id| old_swaps.remove(id)).collect();
+        // The old entries must be removed from the storage before the new ones are written.
+        old_swaps.flush();
+        let mut swaps = UnorderedMap::new(StorageKey::Swaps);
+        for swap in swaps_v4 {
+            swaps.insert(swap.id, Swap::from(swap));
+        }
+
+        Self {
+            owner_id: contract.owner_id,
+            pending_owner_id: contract.pending_owner_id,
+            guardian_id: contract.guardian_id,
+            paused: contract.paused,
+            nft_id: contract.nft_id,
+            nft_ids: contract.nft_ids,
+            ft_ids: contract.ft_ids,
+            fee_config: contract.fee_config,
+            order_limits: contract.order_limits,
+            asks: contract.asks,
+            public_asks,
+            bids: contract.bids,
+            ask_currencies: contract.ask_currencies,
+            reserved_buyers: contract.reserved_buyers,
+            bid_currencies: contract.bid_currencies,
+            auctions: contract.auctions,
+            dutch_auctions: contract.dutch_auctions,
+            offers: contract.offers,
+            offers_by_account: contract.offers_by_account,
+            next_offer_id: contract.next_offer_id,
+            bundles: contract.bundles,
+            bundles_by_owner: contract.bundles_by_owner,
+            next_bundle_id: contract.next_bundle_id,
+            swaps,
+            swaps_by_account: contract.swaps_by_account,
+            next_swap_id: contract.next_swap_id,
+            rentals: contract.rentals,
+            rentals_by_owner: contract.rentals_by_owner,
+            asks_by_owner: contract.asks_by_owner,
+            bids_by_account: contract.bids_by_account,
+            tokens_by_collection,
+            storage_deposits: contract.storage_deposits,
+            storage_used: contract.storage_used,
+            order_storage: contract.order_storage,
+            claimable_balances: contract.claimable_balances,
+            claimable_ft_balances: contract.claimable_ft_balances,
+            claimable_tokens: LookupMap::new(StorageKey::ClaimableTokens),
+            trade_history: contract.trade_history,
+        }
+    }
+}
+
+/// The market state in every layout it has been deployed with.
+///
+/// When the layout of `Contract` changes, freeze the old layout as `ContractV5`,
+/// read it into its own variant and convert it in `into_current`.
+pub(crate) enum VersionedContract {
+    V0(ContractV0),
+    /// The `V2` layout, but the bids of a token are stored in the order they were placed.
+    V1(ContractV2),
+    V2(ContractV2),
+    V3(ContractV3),
+    V4(ContractV4),
+    V5(Contract),
+}
+
+impl VersionedContract {
+    /// Deployments made before versioning have no stored version and use the `V0` layout.
+    fn read() -> Result<Self, ContractError> {
+        match stored_state_version().unwrap_or(0) {
+            0 => read_state().map(Self::V0),
+            1 => read_state().map(Self::V1),
+            2 => read_state().map(Self::V2),
+            3 => read_state().map(Self::V3),
+            4 => read_state().map(Self::V4),
+            5 => read_state().map(Self::V5),
+            version => Err(ContractError::MigrationError(format!(
+                "Unknown state version {version}"
+            ))),
+        }
+    }
+
+    /// The `V0` market has no owner.
+    fn owner_id(&self) -> Option<&AccountId> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(contract) | Self::V2(contract) => Some(&contract.owner_id),
+            Self::V3(contract) => Some(&contract.owner_id),
+            Self::V4(contract) => Some(&contract.owner_id),
+            Self::V5(contract) => Some(&contract.owner_id),
+        }
+    }
+
+    /// `owner_id` becomes the owner of the `V0` market.
+    fn into_current(self, owner_id: AccountId) -> Contract {
+        match self {
+            Self::V0(contract) => {
+                ContractV4::from(ContractV3::from(ContractV2::from_v0(contract, owner_id))).into()
+            }
+            Self::V1(mut contract) => {
+                for bids in contract.bids.values_mut() {
+                    bids.sort_by(cmp_bids);
+                }
+                ContractV4::from(ContractV3::from(contract)).into()
+            }
+            Self::V2(contract) => ContractV4::from(ContractV3::from(contract)).into(),
+            Self::V3(contract) => ContractV4::from(contract).into(),
+            Self::V4(contract) => contract.into(),
+            Self::V5(contract) => contract,
+        }
+    }
+}
+
+fn read_state<T: BorshDeserialize>() -> Result<T, ContractError> {
+    env::state_read()
+        .ok_or_else(|| ContractError::MigrationError("The market isn't initialized".into()))
+}
+
+pub(crate) fn stored_state_version() -> Option<u32> {
+    env::storage_read(STATE_VERSION_KEY).map(|bytes| {
+        u32::try_from_slice(&bytes).unwrap_or_else(|_| env::panic_str("Invalid state version"))
+    })
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Convert the stored state into the current layout after new code is deployed.
+    ///
+    /// Only the market owner or the market itself, via `upgrade`, can migrate.
+    /// The market deployed before versioning has no owner, the caller becomes the owner,
+    /// so the new code must be deployed and migrated in one transaction.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read().unwrap_or_else(|e| e.panic());
+        let predecessor_id = env::predecessor_account_id();
+        if matches!(state.owner_id(), Some(owner_id) if owner_id != &predecessor_id)
+            && predecessor_id != env::current_account_id()
+        {
+            ContractError::NotAuthorized("Only the market owner can migrate the state").panic();
+        }
+
+        let from_version = stored_state_version().unwrap_or(0);
+        write_state_version();
+        emit_event(MarketEvent::StateMigrated {
+            from_version,
+            to_version: STATE_VERSION,
+        });
+        state.into_current(predecessor_id)
+    }
+
+    /// Deploy the new code and migrate the state in one batch, a failed migration reverts the deploy.
+    #[handle_result]
+    pub fn upgrade(&self, code: Base64VecU8) -> Result<Promise, ContractError> {
+        self.check_owner()?;
+
+        Ok(Promise::new(env::current_account_id())
+            .deploy_contract(code.into())
+            .function_call("migrate".to_string(), Vec::new(), NO_DEPOSIT, MIGRATE_GAS))
+    }
+
+    pub fn state_version(&self) -> u32 {
+        stored_state_version().unwrap_or(0)
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::consts::STORAGE_PER_SALE;
+    use crate::test_utils::{get_context, DEPOSIT};
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    /// Store the contract in the layout before rentals, as if it was deployed with `version`.
+    fn write_v2_state(contract: Contract, version: Option<u32>) {
+        let mut new_swaps = contract.swaps;
+        let old_swaps: Vec<SwapV4> = new_swaps
+            .values()
+            .map(|swap| SwapV4 {
+                id: swap.id,
+                maker_id: swap.maker_id.clone(),
+                nft_contract_id: swap.nft_contract_id.clone(),
+                token_id: swap.token_id.clone(),
+                approval_id: swap.approval_id,
+                wanted_nft_contract_id: swap.wanted_nft_contract_id.clone(),
+                wanted_token_id: swap.wanted_token_id.clone(),
+                price: swap.price,
+            })
+            .collect();
+        new_swaps.clear();
+        new_swaps.flush();
+        let mut swaps = UnorderedMap::new(StorageKey::Swaps);
+        for swap in old_swaps {
+            swaps.insert(swap.id, swap);
+        }
+
+        env::state_write(&ContractV2 {
+            owner_id: contract.owner_id,
+            pending_owner_id: contract.pending_owner_id,
+            guardian_id: contract.guardian_id,
+            paused: contract.paused,
+            nft_id: contract.nft_id,
+            nft_ids: contract.nft_ids,
+            ft_ids: contract.ft_ids,
+            fee_config: contract.fee_config,
+            asks: contract.asks,
+            bids: contract.bids,
+            ask_currencies: contract.ask_currencies,
+            reserved_buyers: contract.reserved_buyers,
+            bid_currencies: contract.bid_currencies,
+            auctions: contract.auctions,
+            dutch_auctions: contract.dutch_auctions,
+            offers: contract.offers,
+            offers_by_account: contract.offers_by_account,
+            next_offer_id: contract.next_offer_id,
+            bundles: contract.bundles,
+            bundles_by_owner: contract.bundles_by_owner,
+            next_bundle_id: contract.next_bundle_id,
+            swaps,
+            swaps_by_account: contract.swaps_by_account,
+            next_swap_id: contract.next_swap_id,
+            asks_by_owner: contract.asks_by_owner,
+            bids_by_account: contract.bids_by_account,
+            storage_deposits: contract.storage_deposits,
+            storage_used: contract.storage_used,
+            order_storage: contract.order_storage,
+            claimable_balances: contract.claimable_balances,
+            claimable_ft_balances: contract.claimable_ft_balances,
+            trade_history: contract.trade_history,
+        });
+        match version {
+            Some(version) => env::storage_write(STATE_VERSION_KEY, &version.try_to_vec().unwrap()),
+            None => env::storage_remove(STATE_VERSION_KEY),
+        };
+    }
+
+    #[test]
+    fn state_is_migrated_by_owner() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(2));
+        contract.add_nft_contract(accounts(3)).unwrap();
+        write_v2_state(contract, Some(1));
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.state_version(), STATE_VERSION);
+        assert_eq!(stored_state_version(), Some(STATE_VERSION));
+        assert_eq!(contract.owner(), &accounts(1));
+        assert_eq!(contract.nft_contracts().len(), 2);
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"battlemon_market","version":"1.0.0","event":"state_migrated","data":{"from_version":1,"to_version":5}}"#
+        );
+    }
+
+    #[test]
+    fn unversioned_state_is_migrated_with_orders() {
+        let mut context = get_context(accounts(4));
+        testing_env!(context.build());
+        let mut asks = UnorderedMap::new(StorageKey::Asks);
+        let ask = AskForContract::new(accounts(3), "1".to_string(), 1, U128(100));
+        asks.insert("1".to_string(), ask);
+        let mut token_bids = Vec::new();
+        for price in [1, 3, 2] {
+            context.attached_deposit(price);
+            testing_env!(context.build());
+            token_bids.push(BidForContract::new("2".to_string(), None));
+        }
+        let mut bids = UnorderedMap::new(StorageKey::Bids);
+        bids.insert("2".to_string(), token_bids);
+        let mut storage_deposits = LookupMap::new(StorageKey::StorageDeposits);
+        storage_deposits.insert(accounts(3), DEPOSIT);
+        env::state_write(&ContractV0 {
+            nft_id: accounts(2),
+            asks,
+            bids,
+            storage_deposits,
+        });
+        assert!(stored_state_version().is_none());
+
+        context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        let contract = Contract::migrate();
+        assert_eq!(contract.state_version(), STATE_VERSION);
+        assert_eq!(contract.owner(), &accounts(1));
+        assert_eq!(contract.nft_contracts(), vec![&accounts(2)]);
+        assert_eq!(
+            contract.ask("1".to_string(), None).unwrap().account_id(),
+            &accounts(3)
+        );
+        assert_eq!(contract.asks_by_owner(accounts(3), None, None).len(), 1);
+        assert_eq!(contract.total_asks(), 1);
+        let prices: Vec<_> = contract
+            .bids_for_token("2".to_string(), None, None, None)
+            .iter()
+            .map(|bid| bid.price())
+            .collect();
+        assert_eq!(prices, vec![3, 2, 1]);
+        assert_eq!(contract.total_bids_by_account(accounts(4)), 3);
+        assert_eq!(
+            contract.storage_balance_of(accounts(3)).unwrap().total,
+            U128(DEPOSIT)
+        );
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"battlemon_market","version":"1.0.0","event":"state_migrated","data":{"from_version":0,"to_version":5}}"#
+        );
+    }
+
+    #[test]
+    fn default_order_limits_are_set_by_migration() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::init(accounts(2));
+        write_v2_state(contract, Some(2));
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.order_limits(), &OrderLimits::default());
+    }
+
+    #[test]
+    fn bids_are_ordered_by_price_after_migration_from_v1() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(2));
+        for price in [1, 3, 2] {
+            context.attached_deposit(STORAGE_PER_SALE * price);
+            testing_env!(context.build());
+            contract.storage_deposit(None, None).unwrap();
+            contract.add_bid("1".to_string(), None, None).unwrap();
+        }
+        let key = (accounts(2), "1".to_string());
+        contract.bids.get_mut(&key).unwrap().reverse();
+        write_v2_state(contract, Some(1));
+
+        let contract = Contract::migrate();
+        assert!(contract.rentals(None, None).is_empty());
+        let prices: Vec<_> = contract
+            .bids_for_token("1".to_string(), None, None, None)
+            .iter()
+            .map(|bid| bid.price() / STORAGE_PER_SALE)
+            .collect();
+        assert_eq!(prices, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn public_asks_are_indexed_by_migration() {
+        let mut context = get_context(accounts(3));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(2));
+        context.attached_deposit(DEPOSIT * 2);
+        testing_env!(context.build());
+        contract.storage_deposit(None, None).unwrap();
+
+        context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        let private_msg = format!(r#"{{"price":"100","buyer_ids":["{}"]}}"#, accounts(4));
+        contract
+            .nft_on_approve(
+                "1".to_string(),
+                accounts(3),
+                1,
+                r#"{"price":"100"}"#.to_string(),
+            )
+            .unwrap();
+        contract
+            .nft_on_approve("2".to_string(), accounts(3), 2, private_msg)
+            .unwrap();
+        contract.public_asks.clear();
+        write_v2_state(contract, Some(2));
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        let contract = Contract::migrate();
+        assert_eq!(contract.total_asks(), 1);
+        let asks = contract.asks(None, None);
+        assert_eq!(asks.len(), 1);
+        assert_eq!(asks[0].order.token_id(), "1");
+        assert_eq!(contract.total_collection_book_tokens(None), 2);
+    }
+
+    #[test]
+    fn swaps_are_stored_in_new_layout_by_migration() {
+        let mut context = get_context(accounts(1));
+        context.attached_deposit(DEPOSIT);
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(2));
+        contract.storage_deposit(Some(accounts(3)), None).unwrap();
+        contract
+            .insert_swap(Swap {
+                id: 0,
+                maker_id: accounts(3),
+                nft_contract_id: accounts(2),
+                token_id: "1".to_string(),
+                approval_id: 1,
+                wanted_nft_contract_id: accounts(2),
+                wanted_token_id: "2".to_string(),
+                price: U128(100),
+                wanted_price: U128(0),
+            })
+            .unwrap();
+        write_v2_state(contract, Some(2));
+
+        let contract = Contract::migrate();
+        let swaps = contract.swaps(None, None);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].price, U128(100));
+        assert_eq!(swaps[0].wanted_price, U128(0));
+        assert_eq!(contract.swaps_by_account(accounts(3), None, None).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the market owner can migrate the state")]
+    fn migrate_fails_for_not_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::init(accounts(2));
+        env::state_write(&contract);
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        Contract::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown state version 6")]
+    fn migrate_fails_for_unknown_version() {
+        let context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::init(accounts(2));
+        env::state_write(&contract);
+        env::storage_write(STATE_VERSION_KEY, &6u32.try_to_vec().unwrap());
+
+        Contract::migrate();
+    }
+
+    #[test]
+    fn upgrade_fails_for_not_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::init(accounts(2));
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.upgrade(vec![0].into()),
+            Err(ContractError::NotAuthorized(_))
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::helpers::paginate;
+use crate::{Contract, ContractExt, TradeRecord};
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+/// An order together with the NFT contract of its token.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OrderView<'a, T> {
+    pub nft_contract_id: &'a AccountId,
+    #[serde(flatten)]
+    pub order: &'a T,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn asks(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<OrderView<AskForContract>> {
+        let asks = self.public_asks.iter().filter_map(|key| {
+            self.asks.get(key).map(|ask| OrderView {
+                nft_contract_id: &key.0,
+                order: ask,
+            })
+        });
+
+        paginate(asks, from_index, limit)
+    }
+
+    /// Number of public asks.
+    pub fn total_asks(&self) -> u64 {
+        self.public_asks.len() as u64
+    }
+
+    pub fn asks_by_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<OrderView<AskForContract>> {
+        let keys = match self.asks_by_owner.get(&account_id) {
+            Some(keys) => keys,
+            None => return Vec::new(),
+        };
+        let asks = keys
+            .iter()
+            .filter(|key| !self.is_private_ask(key))
+            .filter_map(|key| {
+                self.asks.get(key).map(|ask| OrderView {
+                    nft_contract_id: &key.0,
+                    order: ask,
+                })
+            });
+
+        paginate(asks, from_index, limit)
+    }
+
+    pub fn total_asks_by_owner(&self, account_id: AccountId) -> u64 {
+        self.count_asks_for_account(&account_id) as u64
+    }
+
+    pub fn bids_for_token(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&BidForContract> {
+        let bids = self
+            .bids
+            .get(&self.key(nft_contract_id, token_id))
+            .into_iter()
+            .flatten();
+
+        paginate(bids, from_index, limit)
+    }
+
+    pub fn total_bids_for_token(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+    ) -> u64 {
+        self.bids
+            .get(&self.key(nft_contract_id, token_id))
+            .map_or(0, |bids| bids.len() as u64)
+    }
+
+    pub fn bids_by_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<OrderView<BidForContract>> {
+        let keys = match self.bids_by_account.get(&account_id) {
+            Some(keys) => keys,
+            None => return Vec::new(),
+        };
+        let bids = keys.iter().filter_map(|(key, id)| {
+            self.find_bid(key, id).map(|bid| OrderView {
+                nft_contract_id: &key.0,
+                order: bid,
+            })
+        });
+
+        paginate(bids, from_index, limit)
+    }
+
+    pub fn total_bids_by_account(&self, account_id: AccountId) -> u64 {
+        self.bids_by_account
+            .get(&account_id)
+            .map_or(0, |keys| keys.len() as u64)
+    }
+
+    pub fn trade_history(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<&TradeRecord> {
+        self.trade_history.records(from_index, limit)
+    }
+
+    pub fn total_trade_history(&self) -> u64 {
+        self.trade_history.len()
+    }
+
+    pub fn trade_history_by_token(
+        &self,
+        token_id: TokenId,
+        nft_contract_id: Option<AccountId>,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&TradeRecord> {
+        let key = self.key(nft_contract_id, token_id);
+        self.trade_history.records_by_token(&key, from_index, limit)
+    }
+
+    pub fn trade_history_by_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<&TradeRecord> {
+        self.trade_history
+            .records_by_account(&account_id, from_index, limit)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{Contract, ContractError, ContractExt};
+use battlemon_models::market::bid::BidForContract;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, ext_contract, near_bindgen, AccountId, PromiseOrValue};
+
+#[ext_contract(ext_ft_receiver)]
+pub trait FungibleTokenReceiver {
+    /// Called by fungible token contract after `ft_transfer_call` was initiated by
+    /// `sender_id` of the given `amount` with the transfer message given in `msg` field.
+    /// The `amount` of tokens were already transferred to this contract account and ready to be used.
+    ///
+    /// Returns the amount of unused tokens that should be returned to sender.
+    /// If the method fails, the fungible token contract returns the whole amount to the sender.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> Result<PromiseOrValue<U128>, ContractError>;
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+/// Create bid order for token with the transferred amount as the price.
+struct Message {
+    token_id: TokenId,
+    expire_at: Option<u64>,
+    nft_contract_id: Option<AccountId>,
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    #[handle_result]
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> Result<PromiseOrValue<U128>, ContractError> {
+        let ft_contract_id = env::predecessor_account_id();
+        self.check_ft_contract(&ft_contract_id)?;
+
+        let message: Message = near_sdk::serde_json::from_str(&msg)?;
+        let nft_contract_id = message
+            .nft_contract_id
+            .unwrap_or_else(|| self.nft_id.clone());
+        let mut bid = BidForContract::new(message.token_id, message.expire_at);
+        bid.account_id = sender_id;
+        bid.price = amount;
+
+        self.place_bid(nft_contract_id, bid, Some(ft_contract_id))?;
+
+        Ok(PromiseOrValue::Value(U128(0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::serde_json;
+
+    #[test]
+    fn message_deserialization_works_for_add_bid() {
+        let msg = r#"{"token_id":"1","expire_at":null}"#;
+        serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
+    }
+}
@@ -1,4 +1,26 @@
+mod ft_on_transfer;
 mod nft_on_approve;
+mod nft_on_revoke;
+mod on_accept_offer;
+mod on_accept_swap;
+mod on_add_bundle;
+mod on_add_swap;
+mod on_buy_bundle;
+mod on_refund;
+mod on_release;
+mod on_rent;
+mod on_swap;
 mod on_trade;
+pub use ft_on_transfer::*;
 pub use nft_on_approve::*;
+pub use nft_on_revoke::*;
+pub use on_accept_offer::*;
+pub use on_accept_swap::*;
+pub use on_add_bundle::*;
+pub use on_add_swap::*;
+pub use on_buy_bundle::*;
+pub use on_refund::*;
+pub use on_release::*;
+pub use on_rent::*;
+pub use on_swap::*;
 pub use on_trade::*;
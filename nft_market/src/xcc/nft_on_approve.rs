@@ -1,9 +1,11 @@
-use crate::{helpers, Contract, ContractError, ContractExt};
+use crate::swap::exchange_swap_gas;
+use crate::trade::check_prepaid_gas;
+use crate::{Contract, ContractError, ContractExt, Rental, SaleType};
 use battlemon_models::market::ask::AskForContract;
 use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{ext_contract, near_bindgen, AccountId};
+use near_sdk::{env, ext_contract, near_bindgen, AccountId};
 
 #[ext_contract(ext_nft_approval_receiver)]
 pub trait NonFungibleTokenApprovalReceiver {
@@ -29,11 +31,69 @@ pub trait NonFungibleTokenApprovalReceiver {
     ) -> Result<(), ContractError>;
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde", untagged)]
+enum Message {
+    AcceptOffer { accept_offer: AcceptOffer },
+    AcceptBid { accept_bid: String },
+    AcceptBestBid { accept_best_bid: AcceptBestBid },
+    AcceptSwap { accept_swap: AcceptSwap },
+    Rent { rent: RentMessage },
+    Ask(AskMessage),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+/// Sell the token to the maker of the offer.
+struct AcceptOffer {
+    offer_id: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+/// Exchange the token for the maker's token and Nears of the swap.
+struct AcceptSwap {
+    swap_id: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+/// Lend the token for `price_per_period` Nears per `period` nanoseconds, up to `max_periods` at once.
+struct RentMessage {
+    price_per_period: U128,
+    period: u64,
+    #[serde(default = "one_period")]
+    max_periods: u64,
+}
+
+fn one_period() -> u64 {
+    1
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+/// Sell the token to the highest bid in Nears or in the fungible token `ft_contract_id`.
+///
+/// The trade fails if the highest bid is less than `min_price`.
+struct AcceptBestBid {
+    ft_contract_id: Option<AccountId>,
+    min_price: Option<U128>,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 /// Create ask order for token with provided price.
-struct Message {
+///
+/// The price is in the fungible token `ft_contract_id` if provided, otherwise in Nears.
+/// `sale_type` opens an auction instead of selling the token for the price.
+/// A non-empty `buyer_ids` reserves the ask for the listed accounts.
+struct AskMessage {
     price: U128,
+    ft_contract_id: Option<AccountId>,
+    #[serde(default)]
+    sale_type: SaleType,
+    #[serde(default, alias = "buyer_id")]
+    buyer_ids: Vec<AccountId>,
 }
 
 #[near_bindgen]
@@ -46,18 +106,107 @@ impl NonFungibleTokenApprovalReceiver for Contract {
         approval_id: u64,
         msg: String,
     ) -> Result<(), ContractError> {
-        helpers::check_cross_contract_call(&self.nft_id)?;
-        self.check_storage_deposits(&owner_id)?;
-
-        let message: Message = near_sdk::serde_json::from_str(&msg)?;
-        self.add_ask(&AskForContract::new(
-            owner_id,
-            token_id,
-            approval_id,
-            message.price,
-        ));
+        let nft_contract_id = env::predecessor_account_id();
+        self.check_nft_contract(&nft_contract_id)?;
+        self.check_not_paused()?;
+
+        let message = match near_sdk::serde_json::from_str(&msg)? {
+            Message::AcceptOffer { accept_offer } => {
+                return self.accept_offer(
+                    nft_contract_id,
+                    token_id,
+                    owner_id,
+                    approval_id,
+                    accept_offer.offer_id,
+                );
+            }
+            Message::AcceptBid { accept_bid } => {
+                let key = (nft_contract_id, token_id);
+                self.remove_expired_bids(&key);
+
+                return self.accept_bid(key, owner_id, approval_id, &accept_bid);
+            }
+            Message::AcceptBestBid { accept_best_bid } => {
+                let key = (nft_contract_id, token_id);
+                self.remove_expired_bids(&key);
+                let bid = self
+                    .highest_bid_than_ask(&key, accept_best_bid.ft_contract_id.as_ref())
+                    .ok_or(ContractError::NotFound(
+                        "Bids for provided token id don't exist",
+                    ))?;
+                if matches!(accept_best_bid.min_price, Some(min_price) if bid.price() < min_price.0)
+                {
+                    return Err(ContractError::AcceptBidError(
+                        "The highest bid is less than the minimum price",
+                    ));
+                }
 
-        Ok(())
+                return self.accept_bid(key, owner_id, approval_id, &bid.id);
+            }
+            Message::AcceptSwap { accept_swap } => {
+                check_prepaid_gas(exchange_swap_gas())?;
+                return self.exchange_swap(
+                    nft_contract_id,
+                    token_id,
+                    owner_id,
+                    approval_id,
+                    accept_swap.swap_id,
+                    0,
+                );
+            }
+            Message::Rent { rent } => {
+                let key = (nft_contract_id.clone(), token_id.clone());
+                if !self.is_lent_by(&key, &owner_id) {
+                    self.check_storage_deposits(&owner_id)?;
+                    self.check_orders_per_account(&owner_id)?;
+                }
+
+                return self.add_rental(Rental {
+                    nft_contract_id,
+                    token_id,
+                    owner_id,
+                    approval_id,
+                    price_per_period: rent.price_per_period,
+                    period: rent.period,
+                    max_periods: rent.max_periods,
+                    lease: None,
+                });
+            }
+            Message::Ask(message) => message,
+        };
+        // A re-approval updates the owner's ask, which already has its storage paid.
+        let key = (nft_contract_id.clone(), token_id.clone());
+        if !self.is_listed_by(&key, &owner_id) {
+            self.check_storage_deposits(&owner_id)?;
+            self.check_orders_per_account(&owner_id)?;
+        }
+        if let Some(ft_contract_id) = &message.ft_contract_id {
+            self.check_ft_contract(ft_contract_id)?;
+        }
+        match message.sale_type {
+            SaleType::Selling => {}
+            SaleType::Auction { end_at, .. } | SaleType::DutchAuction { end_at, .. }
+                if end_at <= env::block_timestamp() =>
+            {
+                return Err(ContractError::AuctionError(
+                    "The auction's end time is already in the past",
+                ));
+            }
+            SaleType::DutchAuction { floor_price, .. } if floor_price.0 > message.price.0 => {
+                return Err(ContractError::AuctionError(
+                    "The floor price exceeds the start price",
+                ));
+            }
+            _ => {}
+        }
+
+        self.add_ask(
+            &nft_contract_id,
+            &AskForContract::new(owner_id, token_id, approval_id, message.price),
+            message.ft_contract_id,
+            message.sale_type,
+            message.buyer_ids,
+        )
     }
 }
 
@@ -71,4 +220,83 @@ mod tests {
         let msg = r#"{"price":"1000"}"#;
         serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
     }
+
+    #[test]
+    fn message_deserialization_works_for_reserved_ask() {
+        let msg = r#"{"price":"1000","buyer_id":["alice.near","bob.near"]}"#;
+        let message = serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
+        assert!(
+            matches!(message, Message::Ask(AskMessage { buyer_ids, .. }) if buyer_ids.len() == 2)
+        );
+    }
+
+    #[test]
+    fn message_deserialization_works_for_add_ask_in_fungible_token() {
+        let msg = r#"{"price":"1000","ft_contract_id":"juice.near"}"#;
+        let message = serde_json::from_str::<AskMessage>(msg).expect("Failed to deserialization");
+        assert_eq!(message.ft_contract_id, Some("juice.near".parse().unwrap()));
+        assert_eq!(message.sale_type, SaleType::Selling);
+    }
+
+    #[test]
+    fn message_deserialization_works_for_auction() {
+        let msg = r#"{"price":"1000","sale_type":{"auction":{"end_at":1,"min_increment":"10"}}}"#;
+        let message = serde_json::from_str::<AskMessage>(msg).expect("Failed to deserialization");
+        assert_eq!(
+            message.sale_type,
+            SaleType::Auction {
+                end_at: 1,
+                min_increment: U128(10)
+            }
+        );
+    }
+
+    #[test]
+    fn message_deserialization_works_for_rent() {
+        let msg = r#"{"rent":{"price_per_period":"1000","period":3600000000000}}"#;
+        let message = serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
+        assert!(matches!(
+            message,
+            Message::Rent {
+                rent: RentMessage {
+                    price_per_period: U128(1000),
+                    period: 3600000000000,
+                    max_periods: 1,
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn message_deserialization_works_for_accept_offer() {
+        let msg = r#"{"accept_offer":{"offer_id":1}}"#;
+        let message = serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
+        assert!(matches!(
+            message,
+            Message::AcceptOffer {
+                accept_offer: AcceptOffer { offer_id: 1 }
+            }
+        ));
+    }
+
+    #[test]
+    fn message_deserialization_works_for_accept_bid() {
+        let msg = r#"{"accept_bid":"Nj9pCmTs2VMTK3cX5mvkK"}"#;
+        let message = serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
+        assert!(
+            matches!(message, Message::AcceptBid { accept_bid } if accept_bid == "Nj9pCmTs2VMTK3cX5mvkK")
+        );
+
+        let msg = r#"{"accept_best_bid":{"min_price":"1000"}}"#;
+        let message = serde_json::from_str::<Message>(msg).expect("Failed to deserialization");
+        assert!(matches!(
+            message,
+            Message::AcceptBestBid {
+                accept_best_bid: AcceptBestBid {
+                    ft_contract_id: None,
+                    min_price: Some(U128(1000)),
+                }
+            }
+        ));
+    }
 }
@@ -0,0 +1,119 @@
+use crate::events::{emit_event, MarketEvent};
+use crate::{Contract, ContractError, ContractExt};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{env, near_bindgen};
+
+#[near_bindgen]
+impl Contract {
+    /// Drop the ask and the rental which rely on the approval, the NFT contract calls it
+    /// when the token is transferred or the approval is revoked.
+    ///
+    /// Orders which already took a newer approval of the token are kept,
+    /// as well as a leased rental until its rent is paid to the owner.
+    /// Returns whether any order was removed.
+    #[handle_result]
+    pub fn nft_on_revoke(
+        &mut self,
+        token_id: TokenId,
+        approval_id: u64,
+    ) -> Result<bool, ContractError> {
+        let nft_contract_id = env::predecessor_account_id();
+        self.check_nft_contract(&nft_contract_id)?;
+
+        let key = (nft_contract_id, token_id);
+        let mut is_removed = false;
+        if self
+            .asks
+            .get(&key)
+            .map_or(false, |ask| ask.approval_id() == Some(approval_id))
+        {
+            self.drop_ask(&key);
+            is_removed = true;
+        }
+        if self.rentals.get(&key).map_or(false, |rental| {
+            rental.approval_id == approval_id && rental.lease.is_none()
+        }) {
+            if let Some(rental) = self.remove_rental(&key) {
+                emit_event(MarketEvent::RemoveRental(rental));
+            }
+            is_removed = true;
+        }
+
+        Ok(is_removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::list_token;
+    use crate::xcc::NonFungibleTokenApprovalReceiver;
+    use crate::ContractError;
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn revoked_approval_removes_ask_and_its_storage_charge() {
+        let (_, mut contract) = list_token(r#"{"price":"100"}"#);
+        assert!(!contract.nft_on_revoke("1".to_string(), 2).unwrap());
+        assert!(contract.ask("1".to_string(), None).is_some());
+
+        assert!(contract.nft_on_revoke("1".to_string(), 1).unwrap());
+        assert!(contract.ask("1".to_string(), None).is_none());
+        assert_eq!(contract.total_asks_by_owner(accounts(2)), 0);
+        assert_eq!(contract.storage_usage_of(accounts(2)).unwrap().used.0, 0);
+    }
+
+    #[test]
+    fn revoked_approval_removes_rental() {
+        let msg = r#"{"rent":{"price_per_period":"100","period":1000}}"#;
+        let (_, mut contract) = list_token(msg);
+        assert!(contract.rental("1".to_string(), None).is_some());
+        assert!(contract.storage_usage_of(accounts(2)).unwrap().used.0 > 0);
+
+        assert!(contract.nft_on_revoke("1".to_string(), 1).unwrap());
+        assert!(contract.rental("1".to_string(), None).is_none());
+        assert_eq!(contract.storage_usage_of(accounts(2)).unwrap().used.0, 0);
+    }
+
+    #[test]
+    fn revoke_fails_for_not_whitelisted_nft_contract() {
+        let (mut context, mut contract) = list_token(r#"{"price":"100"}"#);
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+
+        assert!(matches!(
+            contract.nft_on_revoke("1".to_string(), 1),
+            Err(ContractError::NotAuthorized(_))
+        ));
+    }
+
+    #[test]
+    fn re_approval_updates_ask_in_place() {
+        let (_, mut contract) = list_token(r#"{"price":"100"}"#);
+        contract
+            .nft_on_approve("1".to_string(), accounts(2), 2, r#"{"price":"200"}"#.into())
+            .unwrap();
+        assert_eq!(contract.ask("1".to_string(), None).unwrap().price(), 200);
+        assert_eq!(contract.total_asks_by_owner(accounts(2)), 1);
+
+        assert!(!contract.nft_on_revoke("1".to_string(), 1).unwrap());
+        assert!(contract.nft_on_revoke("1".to_string(), 2).unwrap());
+    }
+
+    #[test]
+    fn re_approval_keeps_running_auction() {
+        let msg = r#"{"price":"100","sale_type":{"auction":{"end_at":1000,"min_increment":"10"}}}"#;
+        let (_, mut contract) = list_token(msg);
+        contract
+            .nft_on_approve("1".to_string(), accounts(2), 2, r#"{"price":"1"}"#.into())
+            .unwrap();
+
+        assert_eq!(contract.ask("1".to_string(), None).unwrap().price(), 100);
+        assert_eq!(
+            contract.auction("1".to_string(), None).unwrap().end_at,
+            1000
+        );
+        assert!(!contract.nft_on_revoke("1".to_string(), 1).unwrap());
+        assert!(contract.nft_on_revoke("1".to_string(), 2).unwrap());
+    }
+}
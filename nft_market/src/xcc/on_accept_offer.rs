@@ -0,0 +1,44 @@
+use crate::events::{emit_event, MarketEvent};
+use crate::{Contract, ContractExt, Offer};
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::TokenExt;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{near_bindgen, AccountId, PromiseError};
+
+#[near_bindgen]
+impl Contract {
+    /// Settle the accepted offer if the token belongs to the owner and meets the offer's filter,
+    /// otherwise return the offer back to the market.
+    #[private]
+    pub fn on_accept_offer(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        approval_id: u64,
+        offer: Offer,
+        #[callback_result] token: Result<Option<TokenExt>, PromiseError>,
+    ) {
+        let is_valid = matches!(
+            token,
+            Ok(Some(token)) if token.owner_id == owner_id
+                && offer.filter.as_ref().map_or(true, |filter| filter.matches(&token.model))
+        );
+        if !is_valid {
+            // The maker could withdraw the storage deposit while the offer was out of the market.
+            if self.insert_offer(offer.clone()).is_err() {
+                self.remove_offer(offer.id);
+                self.refund(&offer.account_id, offer.price.0, None);
+                emit_event(MarketEvent::RemoveOffer(offer));
+            }
+            return;
+        }
+
+        let ask = AskForContract::new(owner_id, token_id.clone(), approval_id, offer.price);
+        let mut bid = BidForContract::new(token_id, None);
+        bid.account_id = offer.account_id.clone();
+        bid.price = offer.price;
+        emit_event(MarketEvent::RemoveOffer(offer.clone()));
+
+        self.trade(offer.nft_contract_id, ask, bid, false, None);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{Contract, ContractExt};
+use battlemon_models::nft::TokenExt;
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId, PromiseError};
+
+#[near_bindgen]
+impl Contract {
+    /// Exchange the tokens of the swap if `nft_token` confirms that the taker owns the wanted token,
+    /// otherwise refund the taker's Nears.
+    #[private]
+    pub fn on_accept_swap(
+        &mut self,
+        swap_id: u64,
+        taker_id: AccountId,
+        approval_id: u64,
+        payment: U128,
+        #[callback_result] token: Result<Option<TokenExt>, PromiseError>,
+    ) {
+        let is_owner = matches!(token, Ok(Some(token)) if token.owner_id == taker_id);
+        let is_exchanged = is_owner
+            && self.swaps.get(&swap_id).cloned().map_or(false, |swap| {
+                self.exchange_swap(
+                    swap.wanted_nft_contract_id,
+                    swap.wanted_token_id,
+                    taker_id.clone(),
+                    approval_id,
+                    swap_id,
+                    payment.0,
+                )
+                .is_ok()
+            });
+
+        if !is_exchanged && payment.0 > 0 {
+            self.refund(&taker_id, payment.0, None);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::events::{emit_event, MarketEvent};
+use crate::{Bundle, Contract, ContractExt};
+use battlemon_models::nft::TokenExt;
+use near_sdk::serde_json;
+use near_sdk::{env, near_bindgen, PromiseResult};
+
+#[near_bindgen]
+impl Contract {
+    /// List the bundle if `nft_token` confirms that its owner owns every token
+    /// and the owner's deposit covers its storage.
+    #[private]
+    pub fn on_add_bundle(&mut self, bundle: Bundle) {
+        let is_owned = env::promise_results_count() as usize == bundle.items.len()
+            && (0..env::promise_results_count()).all(|i| {
+                matches!(
+                    env::promise_result(i),
+                    PromiseResult::Successful(value) if matches!(
+                        serde_json::from_slice::<Option<TokenExt>>(&value),
+                        Ok(Some(token)) if token.owner_id == bundle.owner_id
+                    )
+                )
+            });
+        if !is_owned {
+            return;
+        }
+        if self.insert_bundle(bundle.clone()).is_err() {
+            self.remove_bundle(bundle.id);
+            return;
+        }
+
+        emit_event(MarketEvent::AddBundle(bundle));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::events::{emit_event, MarketEvent};
+use crate::{Contract, ContractExt, Swap};
+use battlemon_models::nft::TokenExt;
+use near_sdk::{near_bindgen, PromiseError};
+
+#[near_bindgen]
+impl Contract {
+    /// List the swap if `nft_token` confirms that its maker owns the token
+    /// and the maker's deposit covers its storage, otherwise refund the maker's Nears.
+    #[private]
+    pub fn on_add_swap(
+        &mut self,
+        swap: Swap,
+        #[callback_result] token: Result<Option<TokenExt>, PromiseError>,
+    ) {
+        let is_listed = matches!(token, Ok(Some(token)) if token.owner_id == swap.maker_id)
+            && self.insert_swap(swap.clone()).is_ok();
+        if !is_listed {
+            // A swap which doesn't fit into the deposit is already stored.
+            self.remove_swap(swap.id);
+            if swap.price.0 > 0 {
+                self.refund(&swap.maker_id, swap.price.0, None);
+            }
+            return;
+        }
+
+        emit_event(MarketEvent::AddSwap(swap));
+    }
+}
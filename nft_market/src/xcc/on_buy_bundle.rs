@@ -0,0 +1,99 @@
+use crate::bundle::on_bundle_payout_gas;
+use crate::consts::{MAX_LEN_PAYOUT, NFT_PAYOUT_GAS};
+use crate::currency::transfer;
+use crate::escrow::escrowed;
+use crate::events::{emit_event, BundleSale, BundleTradeFailed, MarketEvent};
+use crate::external::{nft, Payout};
+use crate::payout::{distribute_payout, merge_payouts};
+use crate::{Bundle, Contract, ContractExt};
+use near_sdk::json_types::U128;
+use near_sdk::serde_json;
+use near_sdk::{env, near_bindgen, AccountId, Promise, PromiseResult};
+
+#[near_bindgen]
+impl Contract {
+    /// Request the payouts of the bundle's tokens held by the market,
+    /// or, if any token wasn't moved to the market, return the moved ones to the owner
+    /// and refund the buyer.
+    #[private]
+    pub fn on_buy_bundle(&mut self, bundle: Bundle, buyer_id: AccountId, deposit: U128) {
+        let moved = escrowed();
+        let is_moved = moved.len() == bundle.items.len() && moved.iter().all(|moved| *moved);
+
+        if !is_moved {
+            for (item, _) in bundle.items.iter().zip(&moved).filter(|(_, moved)| **moved) {
+                self.release(&bundle.nft_contract_id, &item.token_id, &bundle.owner_id);
+            }
+            self.refund(&buyer_id, deposit.0, None);
+            emit_event(MarketEvent::BundleTradeFailed(BundleTradeFailed {
+                bundle,
+                buyer_id,
+                reason: "Failed to transfer the tokens to the market".to_string(),
+            }));
+            return;
+        }
+
+        // The payouts are requested before the tokens go to the buyer, while the market owns them.
+        let shares = bundle.shares(self.fee_config.calculate(bundle.price.0));
+        let payouts = bundle
+            .items
+            .iter()
+            .zip(shares)
+            .map(|(item, share)| {
+                nft::ext(bundle.nft_contract_id.clone())
+                    .with_static_gas(NFT_PAYOUT_GAS)
+                    .nft_payout(item.token_id.clone(), U128(share), Some(MAX_LEN_PAYOUT))
+            })
+            .reduce(Promise::and)
+            .unwrap();
+        let callback_gas = on_bundle_payout_gas(bundle.items.len());
+        payouts.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(callback_gas)
+                .on_bundle_payout(bundle, buyer_id, deposit),
+        );
+    }
+
+    /// Give the bundle's tokens to the buyer and pay every token's share of the price
+    /// according to its payout, the owner gets the whole share of a token without a payout.
+    #[private]
+    pub fn on_bundle_payout(&mut self, bundle: Bundle, buyer_id: AccountId, deposit: U128) {
+        let payouts = (0..env::promise_results_count())
+            .map(|i| match env::promise_result(i) {
+                PromiseResult::Successful(value) => serde_json::from_slice::<Payout>(&value).ok(),
+                _ => None,
+            })
+            .collect();
+        for item in &bundle.items {
+            self.release(&bundle.nft_contract_id, &item.token_id, &buyer_id);
+        }
+
+        let price = bundle.price.0;
+        let market_fee = self.fee_config.calculate(price);
+        if deposit.0 > price {
+            transfer(None, &buyer_id, deposit.0 - price);
+        }
+        if market_fee > 0 {
+            transfer(None, &self.fee_config.treasury_id, market_fee);
+        }
+        let payout = merge_payouts(&bundle.owner_id, &bundle.shares(market_fee), payouts);
+        let royalty = distribute_payout(&bundle.owner_id, price - market_fee, 0, payout, None);
+
+        // The tokens have new owner, their single asks are no longer valid.
+        for item in &bundle.items {
+            let key = (bundle.nft_contract_id.clone(), item.token_id.clone());
+            self.drop_ask(&key);
+        }
+
+        emit_event(MarketEvent::BundleSale(BundleSale {
+            bundle_id: bundle.id,
+            nft_contract_id: bundle.nft_contract_id.clone(),
+            prev_owner: bundle.owner_id.clone(),
+            curr_owner: buyer_id,
+            token_ids: bundle.token_ids(),
+            price: bundle.price,
+            market_fee: U128(market_fee),
+            royalty: U128(royalty),
+        }));
+    }
+}
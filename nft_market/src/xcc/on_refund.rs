@@ -0,0 +1,40 @@
+use crate::events::{emit_event, MarketEvent, Refund};
+use crate::{Contract, ContractExt};
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId, PromiseError};
+use std::ops::AddAssign;
+
+#[near_bindgen]
+impl Contract {
+    /// Credit the claimable balance of the account if the refund failed.
+    #[private]
+    pub fn on_refund(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        ft_contract_id: Option<AccountId>,
+        #[callback_result] refund: Result<(), PromiseError>,
+    ) {
+        if refund.is_ok() {
+            return;
+        }
+
+        match &ft_contract_id {
+            None => self
+                .claimable_balances
+                .entry(account_id.clone())
+                .or_default()
+                .add_assign(amount.0),
+            Some(ft_contract_id) => self
+                .claimable_ft_balances
+                .entry((account_id.clone(), ft_contract_id.clone()))
+                .or_default()
+                .add_assign(amount.0),
+        }
+        emit_event(MarketEvent::RefundFailed(Refund {
+            account_id,
+            amount,
+            ft_contract_id,
+        }));
+    }
+}
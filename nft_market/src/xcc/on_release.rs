@@ -0,0 +1,77 @@
+use crate::events::{emit_event, MarketEvent, Release};
+use crate::{Contract, ContractExt};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{near_bindgen, AccountId, PromiseError};
+
+#[near_bindgen]
+impl Contract {
+    /// Let the receiver claim the token if it wasn't transferred out of the market.
+    #[private]
+    pub fn on_release(
+        &mut self,
+        nft_contract_id: AccountId,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        #[callback_result] release: Result<(), PromiseError>,
+    ) {
+        if release.is_ok() {
+            return;
+        }
+
+        self.claimable_tokens.insert(
+            (nft_contract_id.clone(), token_id.clone()),
+            receiver_id.clone(),
+        );
+        emit_event(MarketEvent::ReleaseFailed(Release {
+            nft_contract_id,
+            token_id,
+            receiver_id,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::get_context;
+    use crate::{Contract, ContractError};
+    use near_sdk::test_utils::accounts;
+    use near_sdk::{testing_env, PromiseError};
+
+    #[test]
+    fn failed_release_is_claimed_by_receiver() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        contract.on_release(
+            accounts(0),
+            "1".to_string(),
+            accounts(2),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(
+            contract.claimable_token("1".to_string(), None),
+            Some(&accounts(2))
+        );
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1);
+        testing_env!(context.build());
+        assert!(matches!(
+            contract.claim_token("1".to_string(), None),
+            Err(ContractError::NotAuthorized(_))
+        ));
+
+        context.predecessor_account_id(accounts(2));
+        testing_env!(context.build());
+        contract.claim_token("1".to_string(), None).unwrap();
+        assert!(contract.claimable_token("1".to_string(), None).is_none());
+        assert!(matches!(
+            contract.claim_token("1".to_string(), None),
+            Err(ContractError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::currency::transfer;
+use crate::events::{emit_event, MarketEvent, RentFailed};
+use crate::{Contract, ContractExt};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId, PromiseError};
+
+#[near_bindgen]
+impl Contract {
+    /// Keep the lease and return the change if the renter became the token's user,
+    /// otherwise drop the lease and refund the renter.
+    #[private]
+    pub fn on_rent(
+        &mut self,
+        nft_contract_id: AccountId,
+        token_id: TokenId,
+        renter_id: AccountId,
+        deposit: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        let key = (nft_contract_id, token_id);
+        let rental = match self.rentals.get_mut(&key) {
+            Some(rental) if matches!(&rental.lease, Some(lease) if lease.renter_id == renter_id) => {
+                rental
+            }
+            _ => {
+                self.refund(&renter_id, deposit.0, None);
+                return;
+            }
+        };
+
+        if result.is_err() {
+            rental.lease = None;
+            let rental = rental.clone();
+            self.refund(&renter_id, deposit.0, None);
+            emit_event(MarketEvent::RentFailed(RentFailed {
+                rental,
+                renter_id,
+                reason: "Failed to set the token user".to_string(),
+            }));
+            return;
+        }
+
+        let rental = rental.clone();
+        let rent = rental.lease.as_ref().map_or(0, |lease| lease.rent.0);
+        if deposit.0 > rent {
+            transfer(None, &renter_id, deposit.0 - rent);
+        }
+        emit_event(MarketEvent::Rented(rental));
+    }
+}
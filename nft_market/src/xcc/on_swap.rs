@@ -0,0 +1,135 @@
+use crate::currency::transfer;
+use crate::escrow::escrowed;
+use crate::events::{emit_event, MarketEvent, SwapCompleted, SwapFailed};
+use crate::{Contract, ContractExt, Swap};
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId};
+
+#[near_bindgen]
+impl Contract {
+    /// Give the maker's token and Nears to the taker and the wanted token and the taker's Nears
+    /// to the maker, or, if either token wasn't moved to the market, return the tokens
+    /// to their owners and refund the Nears.
+    ///
+    /// A token which can't be transferred stays in the market for `claim_token`.
+    #[private]
+    pub fn on_swap(&mut self, swap: Swap, taker_id: AccountId) {
+        let moved = escrowed();
+        let is_moved = moved.len() == 2 && moved.iter().all(|moved| *moved);
+
+        if !is_moved {
+            if moved.first() == Some(&true) {
+                self.release(&swap.nft_contract_id, &swap.token_id, &swap.maker_id);
+            }
+            if moved.get(1) == Some(&true) {
+                self.release(
+                    &swap.wanted_nft_contract_id,
+                    &swap.wanted_token_id,
+                    &taker_id,
+                );
+            }
+            if swap.price.0 > 0 {
+                self.refund(&swap.maker_id, swap.price.0, None);
+            }
+            if swap.wanted_price.0 > 0 {
+                self.refund(&taker_id, swap.wanted_price.0, None);
+            }
+            emit_event(MarketEvent::SwapFailed(SwapFailed {
+                swap,
+                taker_id,
+                reason: "Failed to transfer the tokens to the market".to_string(),
+            }));
+            return;
+        }
+
+        self.release(&swap.nft_contract_id, &swap.token_id, &taker_id);
+        self.release(
+            &swap.wanted_nft_contract_id,
+            &swap.wanted_token_id,
+            &swap.maker_id,
+        );
+
+        let maker_fee = self.fee_config.calculate(swap.price.0);
+        let taker_fee = self.fee_config.calculate(swap.wanted_price.0);
+        let market_fee = maker_fee + taker_fee;
+        if market_fee > 0 {
+            transfer(None, &self.fee_config.treasury_id, market_fee);
+        }
+        if swap.price.0 > maker_fee {
+            transfer(None, &taker_id, swap.price.0 - maker_fee);
+        }
+        if swap.wanted_price.0 > taker_fee {
+            transfer(None, &swap.maker_id, swap.wanted_price.0 - taker_fee);
+        }
+
+        // Both tokens have new owners, their asks are no longer valid.
+        for key in [
+            (swap.nft_contract_id.clone(), swap.token_id.clone()),
+            (
+                swap.wanted_nft_contract_id.clone(),
+                swap.wanted_token_id.clone(),
+            ),
+        ] {
+            self.drop_ask(&key);
+        }
+
+        emit_event(MarketEvent::SwapCompleted(SwapCompleted {
+            swap,
+            taker_id,
+            market_fee: U128(market_fee),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::get_context;
+    use crate::{Contract, Swap};
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::{testing_env, PromiseError, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+    #[test]
+    fn token_not_returned_by_failed_swap_is_claimable() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::init(accounts(0));
+        let swap = Swap {
+            id: 0,
+            maker_id: accounts(2),
+            nft_contract_id: accounts(0),
+            token_id: "1".to_string(),
+            approval_id: 1,
+            wanted_nft_contract_id: accounts(0),
+            wanted_token_id: "2".to_string(),
+            price: U128(0),
+            wanted_price: U128(100),
+        };
+
+        // The maker's token was moved to the market, the wanted token wasn't.
+        context.predecessor_account_id(accounts(0));
+        testing_env!(
+            context.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+        );
+        contract.on_swap(swap, accounts(3));
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"swap_failed""#)));
+
+        // Returning the maker's token fails too.
+        contract.on_release(
+            accounts(0),
+            "1".to_string(),
+            accounts(2),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(
+            contract.claimable_token("1".to_string(), None),
+            Some(&accounts(2))
+        );
+    }
+}
@@ -1,71 +1,218 @@
-use crate::{Contract, ContractExt};
-use battlemon_models::helpers_contract::emit_log_event;
-use battlemon_models::market::events::MarketEventKind;
+use crate::events::{emit_event, MarketEvent, Sale, TradeFailed};
+use crate::external::Payout;
+use crate::payout::distribute_payout;
+use crate::refund::pay;
+use crate::{Contract, ContractExt, TradeRecord};
 use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
 use near_sdk::json_types::U128;
-use near_sdk::{near_bindgen, Promise, PromiseError};
+use near_sdk::{env, near_bindgen, AccountId, Balance, PromiseError};
 
 #[near_bindgen]
 impl Contract {
     #[private]
     pub fn on_trade(
         &mut self,
+        nft_contract_id: AccountId,
         ask: AskForContract,
         bid: BidForContract,
         change: bool,
-        #[callback_result] trade: Result<(), PromiseError>,
+        market_fee: U128,
+        ft_contract_id: Option<AccountId>,
+        #[callback_result] trade: Result<Payout, PromiseError>,
     ) {
-        trade.unwrap();
-        let to_asker = Promise::new(ask.account_id().to_owned());
-        let trade_price;
-        if change {
-            trade_price = ask.price();
-            let diff = bid.price() - trade_price;
-            let to_bidder = Promise::new(bid.account_id().to_owned()).transfer(diff);
-            to_asker.transfer(trade_price).and(to_bidder);
-        } else {
-            trade_price = bid.price();
-            to_asker.transfer(trade_price);
+        let payout = match trade {
+            Ok(payout) => payout,
+            Err(_) => {
+                // The bid was never stored or was removed before the trade.
+                self.remove_failed_ask(&nft_contract_id, &ask);
+                self.refund(bid.account_id(), bid.price(), ft_contract_id.as_ref());
+
+                emit_event(MarketEvent::TradeFailed(TradeFailed {
+                    nft_contract_id,
+                    ask,
+                    bid,
+                    reason: "Failed to transfer the token to the bidder".to_string(),
+                }));
+                return;
+            }
+        };
+
+        let trade_price = if change { ask.price() } else { bid.price() };
+        let diff = bid.price() - trade_price;
+        if diff > 0 {
+            pay(ft_contract_id.as_ref(), bid.account_id(), diff);
         }
 
-        self.clean_ask_and_bid(&bid);
+        if market_fee.0 > 0 {
+            pay(
+                ft_contract_id.as_ref(),
+                &self.fee_config.treasury_id,
+                market_fee.0,
+            );
+        }
 
-        let trade_for_log = battlemon_models::market::sale::SaleForContract {
-            prev_owner: ask.account_id().to_string(),
-            curr_owner: bid.account_id().to_string(),
-            token_id: ask.token_id().to_string(),
+        // The seller pays for the storage taken by the trade record in Nears trades,
+        // the market covers it in fungible token trades.
+        self.trade_history.flush();
+        let initial_storage_usage = env::storage_usage();
+        self.trade_history.add(TradeRecord {
+            nft_contract_id: nft_contract_id.clone(),
+            token_id: ask.token_id().to_owned(),
+            prev_owner: ask.account_id().to_owned(),
+            curr_owner: bid.account_id().to_owned(),
             price: U128(trade_price),
+            ft_contract_id: ft_contract_id.clone(),
+            date: env::block_timestamp(),
+        });
+        self.trade_history.flush();
+        let storage_cost = match ft_contract_id {
+            None => {
+                Balance::from(env::storage_usage().saturating_sub(initial_storage_usage))
+                    * env::storage_byte_cost()
+            }
+            Some(_) => 0,
         };
 
-        emit_log_event(MarketEventKind::Sale(trade_for_log));
+        // The payout is requested for the price without the fee, so it's distributed in full.
+        let royalty = distribute_payout(
+            ask.account_id(),
+            trade_price - market_fee.0,
+            storage_cost,
+            payout,
+            ft_contract_id.as_ref(),
+        );
+
+        self.clean_ask_and_bid(&nft_contract_id, &bid);
+
+        emit_event(MarketEvent::Sale(Sale {
+            nft_contract_id,
+            prev_owner: ask.account_id().to_owned(),
+            curr_owner: bid.account_id().to_owned(),
+            token_id: ask.token_id().to_owned(),
+            price: U128(trade_price),
+            market_fee,
+            royalty: U128(royalty),
+            ft_contract_id,
+        }));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::events::EventLog;
+    use crate::test_utils::list_token;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
 
     #[test]
     fn sale_for_contract_valid_logs() {
         let expected_json = near_sdk::serde_json::json!({
+            "standard": "battlemon_market",
+            "version": "1.0.0",
             "event": "sale",
             "data": {
+                "nft_contract_id": "nft.near",
                 "prev_owner": "alice.near",
                 "curr_owner": "bob.near",
                 "token_id": "1",
                 "price": U128(10000000000000000000000),
+                "market_fee": U128(250000000000000000000),
+                "royalty": U128(1000000000000000000000),
             }
         });
 
-        let sale_for_contract = battlemon_models::market::sale::SaleForContract {
-            prev_owner: "alice.near".to_string(),
-            curr_owner: "bob.near".to_string(),
+        let sale = Sale {
+            nft_contract_id: "nft.near".parse().unwrap(),
+            prev_owner: "alice.near".parse().unwrap(),
+            curr_owner: "bob.near".parse().unwrap(),
             token_id: "1".to_string(),
             price: U128(10000000000000000000000),
+            market_fee: U128(250000000000000000000),
+            royalty: U128(1000000000000000000000),
+            ft_contract_id: None,
         };
-        let event = MarketEventKind::Sale(sale_for_contract);
-        let actual_json = near_sdk::serde_json::to_value(&event).unwrap();
+        let event = MarketEvent::Sale(sale);
+        let actual_json = near_sdk::serde_json::to_value(&EventLog::new(event)).unwrap();
 
         assert_eq!(actual_json, expected_json);
     }
+
+    #[test]
+    fn trade_failed_valid_logs() {
+        let context = near_sdk::test_utils::VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .attached_deposit(10000000000000000000000)
+            .build();
+        near_sdk::testing_env!(context);
+
+        let ask = AskForContract::new(
+            "alice.near".parse().unwrap(),
+            "1".to_string(),
+            1,
+            U128(10000000000000000000000),
+        );
+        let bid = BidForContract::new("1".to_string(), None);
+        let expected_json = near_sdk::serde_json::json!({
+            "standard": "battlemon_market",
+            "version": "1.0.0",
+            "event": "trade_failed",
+            "data": {
+                "nft_contract_id": "nft.near",
+                "ask": ask,
+                "bid": bid,
+                "reason": "Failed to transfer the token to the bidder",
+            }
+        });
+
+        let event = MarketEvent::TradeFailed(TradeFailed {
+            nft_contract_id: "nft.near".parse().unwrap(),
+            ask,
+            bid,
+            reason: "Failed to transfer the token to the bidder".to_string(),
+        });
+        let actual_json = near_sdk::serde_json::to_value(&EventLog::new(event)).unwrap();
+
+        assert_eq!(actual_json, expected_json);
+    }
+
+    fn failed_trade(contract: &mut Contract, context: &mut VMContextBuilder, approval_id: u64) {
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(100);
+        testing_env!(context.build());
+        let ask = AskForContract::new(accounts(2), "1".to_string(), approval_id, U128(100));
+        let bid = BidForContract::new("1".to_string(), None);
+
+        context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(0);
+        testing_env!(context.build());
+        contract.on_trade(
+            accounts(0),
+            ask,
+            bid,
+            false,
+            U128(0),
+            None,
+            Err(PromiseError::Failed),
+        );
+    }
+
+    #[test]
+    fn failed_trade_removes_its_ask() {
+        let (mut context, mut contract) = list_token(r#"{"price":"100"}"#);
+
+        failed_trade(&mut contract, &mut context, 1);
+        assert!(contract.ask("1".to_string(), None).is_none());
+    }
+
+    #[test]
+    fn failed_trade_keeps_ask_of_another_approval() {
+        let (mut context, mut contract) = list_token(r#"{"price":"100"}"#);
+
+        // The bid was accepted with a new approval, the listing isn't a part of the trade.
+        failed_trade(&mut contract, &mut context, 2);
+        assert!(contract.ask("1".to_string(), None).is_some());
+    }
 }
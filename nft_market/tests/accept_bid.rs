@@ -0,0 +1,95 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::{NftKind, TokenExt};
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn token_owner_accepts_best_bid() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve(
+            "1",
+            &market,
+            Some(r#"{"accept_best_bid":{"min_price":"5000000000000000000000000"}}"#),
+        )?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(150))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_nft_token")
+        .then()
+        .view_market_contract_bids_for_token("1", None, None, None)?
+        .with_label("view_bids")
+        .then()
+        .view_market_contract_asks(None, None)?
+        .with_label("view_asks")
+        .then()
+        .view_account(ALICE)?
+        .with_label("view_alice")
+        .execute()
+        .await?;
+
+    let nft_token = result.tx("view_nft_token")?.json::<TokenExt>()?;
+    assert_eq!(nft_token.owner_id.as_str(), bob.as_str());
+    assert!(result
+        .tx("view_bids")?
+        .json::<Vec<BidForContract>>()?
+        .is_empty());
+    assert!(result
+        .tx("view_asks")?
+        .json::<Vec<AskForContract>>()?
+        .is_empty());
+
+    let alice_balance = result.tx("view_alice")?.balance();
+    let diff = Near(15) - alice_balance;
+    assert!(
+        diff <= ALMOST_ZERO,
+        "Expected alice balance isn't less than 0.1 N, actual balance is {}",
+        alice_balance
+    );
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use helpers::{MARKET, MARKET_PATH, NFT};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn paused_market_rejects_bids_but_allows_withdrawals() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let result = bchain
+        .call_market_contract_init(NFT)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .call_market_contract_pause()?
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_is_paused()?
+        .with_label("is_paused")
+        .then()
+        .alice_call_market_contract_storage_withdraw(None)?
+        .with_deposit(1)
+        .with_gas(Tgas(10))
+        .then()
+        .view_account(ALICE)?
+        .with_label("view_alice")
+        .execute()
+        .await?;
+
+    assert!(result.tx("is_paused")?.json::<bool>()?);
+    let alice_balance = result.tx("view_alice")?.balance();
+    assert!((Near(10) - alice_balance) <= ALMOST_ZERO);
+
+    let result = result
+        .into_state()
+        .alice_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("The market is paused"));
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::{NftKind, TokenExt};
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use nft_market::{Bundle, BundleItem};
+use std::collections::HashMap;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn bundle_is_sold_as_one_lot() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+    let items = vec![
+        BundleItem {
+            token_id: "1".to_string(),
+            approval_id: 1,
+        },
+        BundleItem {
+            token_id: "2".to_string(),
+            approval_id: 1,
+        },
+    ];
+
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("2", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_add_bundle(None, items, U128(Near(5)))?
+        .with_gas(Tgas(50))
+        .with_label("add_bundle")
+        .then()
+        .view_market_contract_bundles(None, None)?
+        .with_label("view_bundles")
+        .execute()
+        .await?;
+
+    let bundle_id = result.tx("add_bundle")?.json::<u64>()?;
+    let bundles = result.tx("view_bundles")?.json::<Vec<Bundle>>()?;
+    assert_eq!(bundles.len(), 1);
+    assert_eq!(bundles[0].owner_id.as_str(), alice.as_str());
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_buy_bundle(bundle_id)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(150))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_lemon")
+        .then()
+        .view_nft_contract_nft_token("2")?
+        .with_label("view_fire_arm")
+        .then()
+        .view_market_contract_bundle(bundle_id)?
+        .with_label("view_bundle")
+        .execute()
+        .await?;
+
+    let lemon = result.tx("view_lemon")?.json::<TokenExt>()?;
+    assert_eq!(lemon.owner_id.as_str(), bob.as_str());
+    let fire_arm = result.tx("view_fire_arm")?.json::<TokenExt>()?;
+    assert_eq!(fire_arm.owner_id.as_str(), bob.as_str());
+    assert!(result
+        .tx("view_bundle")?
+        .json::<Option<Bundle>>()?
+        .is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bundle_sale_pays_royalties() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let royalties = HashMap::from([(nft.parse::<AccountId>()?, 1_000)]);
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_nft_contract_set_royalties(royalties)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+    let items = vec![
+        BundleItem {
+            token_id: "1".to_string(),
+            approval_id: 1,
+        },
+        BundleItem {
+            token_id: "2".to_string(),
+            approval_id: 1,
+        },
+    ];
+
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("2", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_add_bundle(None, items, U128(Near(5)))?
+        .with_gas(Tgas(50))
+        .with_label("add_bundle")
+        .then()
+        .view_account(ALICE)?
+        .with_label("alice_before")
+        .execute()
+        .await?;
+
+    let bundle_id = result.tx("add_bundle")?.json::<u64>()?;
+    let alice_before = result.tx("alice_before")?.balance();
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_buy_bundle(bundle_id)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(150))
+        .then()
+        .view_nft_contract_nft_token("2")?
+        .with_label("view_fire_arm")
+        .then()
+        .view_account(ALICE)?
+        .with_label("alice_after")
+        .execute()
+        .await?;
+
+    let fire_arm = result.tx("view_fire_arm")?.json::<TokenExt>()?;
+    assert_eq!(fire_arm.owner_id.as_str(), bob.as_str());
+    // 10% of every token's share goes to the royalty holder.
+    let alice_after = result.tx("alice_after")?.balance();
+    assert_eq!(alice_after - alice_before, Near(45) / 10);
+
+    Ok(())
+}
@@ -56,15 +56,24 @@ async fn cancel_bid_works() -> anyhow::Result<()> {
     let result = result
         .into_state()
         .alice_call_market_contract_cancel_bid(&token_id, &bid_id)?
-        .with_gas(Tgas(10))
+        .with_gas(Tgas(30))
         .then()
         .view_market_contract_bids(token_id.as_str())?
         .with_label("bids_after")
+        .then()
+        .view_account(ALICE)?
+        .with_label("alice_balance")
         .execute()
         .await?;
 
     let bids_after: Option<Vec<AskForContract>> = result.tx("bids_after")?.json()?;
     assert!(bids_after.is_none());
+    let alice_balance = result.tx("alice_balance")?.balance();
+    assert!(
+        (Near(10) - alice_balance) <= ALMOST_ZERO,
+        "Expected alice's bid to be refunded, actual balance is {}",
+        alice_balance
+    );
 
     Ok(())
 }
@@ -116,7 +125,7 @@ async fn cancel_bids_works_only_for_owner() -> anyhow::Result<()> {
     let result = result
         .into_state()
         .bob_call_market_contract_cancel_bid(&token_id, &bid_id)?
-        .with_gas(Tgas(10))
+        .with_gas(Tgas(30))
         .execute()
         .await;
 
@@ -2,3 +2,11 @@ pub const NFT_PATH: &str = "../target/wasm32-unknown-unknown/release/nft_token.w
 pub const MARKET_PATH: &str = "../target/wasm32-unknown-unknown/release/nft_market.wasm";
 pub const NFT: &str = "nft_contract";
 pub const MARKET: &str = "market_contract";
+pub const SPOILED_NFT_PATH: &str =
+    "../target/wasm32-unknown-unknown/release/spoiled_nft_token.wasm";
+pub const SPOILED_NFT: &str = "spoiled_nft_contract";
+pub const JUICE_PATH: &str = "../target/wasm32-unknown-unknown/release/juice.wasm";
+pub const JUICE: &str = "juice_contract";
+/// The contracts deployed before state versioning, built by `scripts/build_baseline_wasm.sh`.
+pub const BASELINE_NFT_PATH: &str = "../res/baseline/nft_token.wasm";
+pub const BASELINE_MARKET_PATH: &str = "../res/baseline/nft_market.wasm";
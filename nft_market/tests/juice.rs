@@ -0,0 +1,183 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::{NftKind, TokenExt};
+use helpers::{JUICE, JUICE_PATH, MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_sdk::json_types::U128;
+
+add_helpers!(
+    "./nft_schema.json",
+    "./market_schema.json",
+    "./juice_schema.json",
+);
+
+#[tokio::test]
+async fn bid_in_juice_buys_token_asked_in_juice() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_contract(JUICE, JUICE_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, juice, alice, bob] = bchain.string_ids()?;
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_add_ft_contract(&juice)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_juice_contract_init(&juice, U128(100000))?
+        .with_gas(Tgas(10))
+        .then()
+        .call_juice_contract_storage_deposit(Some(&alice))?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .call_juice_contract_storage_deposit(Some(&bob))?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .call_juice_contract_storage_deposit(Some(&market))?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .call_juice_contract_ft_transfer(&bob, U128(10000), None)?
+        .with_gas(Tgas(10))
+        .with_deposit(1)
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let ask_msg = format!("{{\"price\":\"1000\",\"ft_contract_id\":\"{}\"}}", juice);
+    let bid_msg = r#"{"token_id":"1"}"#;
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&ask_msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .bob_call_juice_contract_ft_transfer_call(&market, U128(1500), bid_msg)?
+        .with_deposit(1)
+        .with_gas(Tgas(300))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_nft_token")
+        .then()
+        .view_juice_contract_ft_balance_of(&alice)?
+        .with_label("alice_juice")
+        .then()
+        .view_juice_contract_ft_balance_of(&bob)?
+        .with_label("bob_juice")
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("view_ask")
+        .execute()
+        .await?;
+
+    let nft_token = result.tx("view_nft_token")?.json::<TokenExt>()?;
+    assert_eq!(nft_token.owner_id.as_str(), bob.as_str());
+    assert_eq!(result.tx("alice_juice")?.json::<U128>()?, U128(1000));
+    assert_eq!(result.tx("bob_juice")?.json::<U128>()?, U128(9000));
+    assert!(result
+        .tx("view_ask")?
+        .json::<Option<AskForContract>>()?
+        .is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bid_in_nears_is_not_matched_with_ask_in_juice() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_contract(JUICE, JUICE_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, juice, alice, _bob] = bchain.string_ids()?;
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_add_ft_contract(&juice)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let ask_msg = format!("{{\"price\":\"1000\",\"ft_contract_id\":\"{}\"}}", juice);
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&ask_msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(200))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_nft_token")
+        .then()
+        .view_market_contract_bids("1")?
+        .with_label("view_bids")
+        .execute()
+        .await?;
+
+    let nft_token = result.tx("view_nft_token")?.json::<TokenExt>()?;
+    assert_eq!(nft_token.owner_id.as_str(), alice.as_str());
+    let bids = result
+        .tx("view_bids")?
+        .json::<Option<Vec<BidForContract>>>()?
+        .unwrap();
+    assert_eq!(bids.len(), 1);
+
+    Ok(())
+}
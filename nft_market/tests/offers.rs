@@ -0,0 +1,96 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::{NftKind, TokenExt};
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use nft_market::Offer;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn token_owner_accepts_floor_offer() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .bob_call_market_contract_add_offer(None)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(10))
+        .with_label("add_offer")
+        .then()
+        .view_market_contract_offers_by_account(&bob, None, None)?
+        .with_label("view_offers")
+        .execute()
+        .await?;
+
+    let offer_id = result.tx("add_offer")?.json::<u64>()?;
+    let offers = result.tx("view_offers")?.json::<Vec<Offer>>()?;
+    assert_eq!(offers.len(), 1);
+    assert_eq!(offers[0].price, U128(Near(5)));
+
+    let msg = format!("{{\"accept_offer\":{{\"offer_id\":{}}}}}", offer_id);
+    let result = result
+        .into_state()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(200))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_nft_token")
+        .then()
+        .view_market_contract_offer(offer_id)?
+        .with_label("view_offer")
+        .then()
+        .view_account(ALICE)?
+        .with_label("view_alice")
+        .execute()
+        .await?;
+
+    let nft_token = result.tx("view_nft_token")?.json::<TokenExt>()?;
+    assert_eq!(nft_token.owner_id.as_str(), bob.as_str());
+    assert!(result.tx("view_offer")?.json::<Option<Offer>>()?.is_none());
+
+    let alice_balance = result.tx("view_alice")?.balance();
+    let diff = Near(15) - alice_balance;
+    assert!(
+        diff <= ALMOST_ZERO,
+        "Expected alice balance isn't less than 0.1 N, actual balance is {}",
+        alice_balance
+    );
+
+    Ok(())
+}
@@ -7,6 +7,7 @@ use lemotests::prelude::*;
 use lemotests_macro::add_helpers;
 use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::json_types::U128;
+use nft_market::TradeRecord;
 
 add_helpers!("./nft_schema.json", "./market_schema.json",);
 
@@ -115,6 +116,9 @@ async fn alice_ask_for_nft_token_five_bob_bid_six_alice_receive_five_bob_receive
         .view_nft_contract_nft_token("1")?
         .with_label("view_nft_token")
         .then()
+        .view_market_contract_trade_history_by_token("1", None, None, None)?
+        .with_label("view_trade_history")
+        .then()
         .view_account(ALICE)?
         .with_label("view_alice")
         .then()
@@ -126,6 +130,14 @@ async fn alice_ask_for_nft_token_five_bob_bid_six_alice_receive_five_bob_receive
     let nft_token = result.tx("view_nft_token")?.json::<TokenExt>()?;
     assert_eq!(nft_token.owner_id.as_str(), bob.as_str());
 
+    let trade_history = result
+        .tx("view_trade_history")?
+        .json::<Vec<TradeRecord>>()?;
+    assert_eq!(trade_history.len(), 1);
+    assert_eq!(trade_history[0].prev_owner.as_str(), alice.as_str());
+    assert_eq!(trade_history[0].curr_owner.as_str(), bob.as_str());
+    assert_eq!(trade_history[0].price, U128(Near(5)));
+
     let alice_balance = result.tx("view_alice")?.balance();
     let bob_balance = result.tx("view_bob")?.balance();
 
@@ -195,7 +207,7 @@ async fn bob_bid_six_alice_ask_for_nft_token_five_alice_receive_six_bob_receive_
         .then()
         .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
         .with_deposit(Near(5))
-        .with_gas(Tgas(50))
+        .with_gas(Tgas(150))
         .then()
         .view_nft_contract_nft_token("1")?
         .with_label("view_nft_token")
@@ -283,7 +295,7 @@ async fn bid_first_ask_second_then_trade_then_bid_is_removed_and_ask_does_not_cr
         .then()
         .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
         .with_deposit(Near(5))
-        .with_gas(Tgas(50))
+        .with_gas(Tgas(150))
         .then()
         .view_market_contract_bids("1")?
         .with_label("bids_after")
@@ -354,7 +366,7 @@ async fn ask_first_bid_second_then_trade_then_ask_is_removed_and_bid_does_not_cr
         .then()
         .bob_call_market_contract_add_bid("1", None)?
         .with_deposit(Near(5))
-        .with_gas(Tgas(40))
+        .with_gas(Tgas(150))
         .then()
         .view_market_contract_ask("1")?
         .with_label("ask_after")
@@ -0,0 +1,120 @@
+mod helpers;
+
+use battlemon_models::market::bid::BidForContract;
+use battlemon_models::nft::NftKind;
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests::Nearable;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use nft_market::{OrderBook, PriceLevel};
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn order_book_aggregates_bids_and_asks_by_price() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_best_bid("1", None, None)?
+        .with_label("best_bid")
+        .then()
+        .view_market_contract_order_book("1", None, None, None)?
+        .with_label("order_book")
+        .then()
+        .view_market_contract_collection_order_book(None, None, Some(1), None, None)?
+        .with_label("collection_order_book")
+        .then()
+        .view_market_contract_total_collection_book_tokens(None)?
+        .with_label("total_collection_book_tokens")
+        .execute()
+        .await?;
+
+    let best_bid: BidForContract = result.tx("best_bid")?.json()?;
+    assert_eq!(best_bid.price(), Near(2).parse());
+    assert_eq!(best_bid.account_id().as_str(), bob.as_str());
+
+    let order_book: OrderBook = result.tx("order_book")?.json()?;
+    assert_eq!(order_book.best_bid, Some(U128(Near(2).parse())));
+    assert_eq!(order_book.best_ask, Some(U128(Near(5).parse())));
+    assert_eq!(order_book.spread, Some(U128(Near(3).parse())));
+    assert_eq!(
+        order_book.bids,
+        vec![
+            PriceLevel {
+                price: U128(Near(2).parse()),
+                orders: 2,
+            },
+            PriceLevel {
+                price: U128(Near(1).parse()),
+                orders: 1,
+            },
+        ]
+    );
+
+    let collection_order_book: OrderBook = result.tx("collection_order_book")?.json()?;
+    assert_eq!(collection_order_book.bids.len(), 1);
+    assert_eq!(collection_order_book.asks, order_book.asks);
+    assert_eq!(result.tx("total_collection_book_tokens")?.json::<u64>()?, 1);
+
+    let token_id = TokenId::from("1");
+    let result = result
+        .into_state()
+        .bob_call_market_contract_cancel_bid(&token_id, &best_bid.id)?
+        .with_gas(Tgas(30))
+        .then()
+        .view_market_contract_best_bid(token_id.as_str(), None, None)?
+        .with_label("best_bid")
+        .execute()
+        .await?;
+
+    let next_best_bid: BidForContract = result.tx("best_bid")?.json()?;
+    assert_eq!(next_best_bid.price(), Near(2).parse());
+    assert_ne!(next_best_bid.id, best_bid.id);
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+mod helpers;
+
+use battlemon_models::market::bid::BidForContract;
+use helpers::{MARKET, MARKET_PATH, NFT};
+use lemotests::prelude::*;
+use lemotests::Nearable;
+use lemotests_macro::add_helpers;
+use near_sdk::json_types::U128;
+use nft_market::OrderLimits;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn bids_below_limits_are_rejected() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let order_limits = OrderLimits {
+        min_bid_price: Some(U128(Near(1).parse())),
+        max_bids_per_token: Some(1),
+        max_orders_per_account: None,
+        min_bid_increment: Some(U128(Near(1).parse())),
+    };
+
+    let result = bchain
+        .call_market_contract_init(NFT)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_set_order_limits(order_limits.clone())?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_order_limits()?
+        .with_label("order_limits")
+        .then()
+        .view_market_contract_bids("1")?
+        .with_label("bids")
+        .execute()
+        .await?;
+
+    assert_eq!(
+        result.tx("order_limits")?.json::<OrderLimits>()?,
+        order_limits
+    );
+    let bids: Option<Vec<BidForContract>> = result.tx("bids")?.json()?;
+    assert_eq!(bids.map(|bids| bids.len()), Some(1));
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_add_bid("2", None)?
+        .with_deposit(1)
+        .with_gas(Tgas(10))
+        .execute()
+        .await;
+    assert!(result.contains_error("The bid's price is less than the minimum"));
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+mod helpers;
+
+use battlemon_models::nft::NftKind;
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests::Nearable;
+use lemotests_macro::add_helpers;
+use nft_market::Rental;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+/// One hour in nanoseconds.
+const PERIOD: u64 = 3_600_000_000_000;
+
+#[tokio::test]
+async fn renter_becomes_token_user_until_lease_expires() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let msg = format!(
+        "{{\"rent\":{{\"price_per_period\":\"{}\",\"period\":{PERIOD},\"max_periods\":2}}}}",
+        Near(1)
+    );
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .bob_call_market_contract_rent("1", None, 2)?
+        .with_deposit(Near(3))
+        .with_gas(Tgas(50))
+        .then()
+        .view_market_contract_rental("1", None)?
+        .with_label("rental")
+        .then()
+        .view_nft_contract_nft_user_of("1")?
+        .with_label("user")
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("token")
+        .execute()
+        .await?;
+
+    let rental: Option<Rental> = result.tx("rental")?.json()?;
+    let lease = rental.and_then(|rental| rental.lease).unwrap();
+    assert_eq!(lease.renter_id.as_str(), bob.as_str());
+    assert_eq!(lease.rent.0, Near(2).parse());
+    let user: Option<String> = result.tx("user")?.json()?;
+    assert_eq!(user.as_deref(), Some(bob.as_str()));
+    let token: serde_json::Value = result.tx("token")?.json()?;
+    assert_eq!(token["owner_id"].as_str(), Some(alice.as_str()));
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_end_rental("1", None)?
+        .with_gas(Tgas(50))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("The token isn't leased or the lease hasn't expired yet"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn leased_token_can_not_be_transferred() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let msg = format!(
+        "{{\"rent\":{{\"price_per_period\":\"{}\",\"period\":{PERIOD}}}}}",
+        Near(1)
+    );
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .bob_call_market_contract_rent("1", None, 1)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .execute()
+        .await?;
+
+    let result = result
+        .into_state()
+        .alice_call_nft_contract_nft_transfer(&bob, "1", None, None)?
+        .with_deposit(1)
+        .with_gas(Tgas(50))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("is used by"));
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+mod helpers;
+
+use battlemon_models::market::ask::AskForContract;
+use battlemon_models::nft::NftKind;
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests::Nearable;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn ask_is_removed_when_token_is_transferred_or_revoked() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .alice_call_nft_contract_nft_approve("2", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .view_market_contract_total_asks()?
+        .with_label("total_asks_before")
+        .then()
+        .alice_call_nft_contract_nft_transfer(&bob, "1", None, None)?
+        .with_deposit(1)
+        .with_gas(Tgas(50))
+        .then()
+        .alice_call_nft_contract_nft_revoke("2", &market)?
+        .with_deposit(1)
+        .with_gas(Tgas(50))
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("transferred_ask")
+        .then()
+        .view_market_contract_ask("2")?
+        .with_label("revoked_ask")
+        .then()
+        .view_market_contract_total_asks()?
+        .with_label("total_asks_after")
+        .execute()
+        .await?;
+
+    assert_eq!(result.tx("total_asks_before")?.json::<u64>()?, 2);
+    let transferred_ask: Option<AskForContract> = result.tx("transferred_ask")?.json()?;
+    assert!(transferred_ask.is_none());
+    let revoked_ask: Option<AskForContract> = result.tx("revoked_ask")?.json()?;
+    assert!(revoked_ask.is_none());
+    assert_eq!(result.tx("total_asks_after")?.json::<u64>()?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn re_approval_updates_ask_in_place() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice] = bchain.string_ids()?;
+    let token_id = TokenId::from("1");
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+    let new_msg = format!("{{\"price\":\"{}\"}}", Near(3));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve(&token_id, &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .view_market_contract_ask(&token_id)?
+        .with_label("ask_before")
+        .then()
+        .alice_call_nft_contract_nft_approve(&token_id, &market, Some(&new_msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .view_market_contract_ask(&token_id)?
+        .with_label("ask_after")
+        .then()
+        .view_market_contract_total_asks_by_owner(&alice)?
+        .with_label("total_asks")
+        .execute()
+        .await?;
+
+    let ask_before: AskForContract = result.tx("ask_before")?.json()?;
+    let ask_after: AskForContract = result.tx("ask_after")?.json()?;
+    assert_ne!(ask_after.approval_id, ask_before.approval_id);
+    assert_eq!(ask_after.price.0, Near(3).parse());
+    assert_eq!(result.tx("total_asks")?.json::<u64>()?, 1);
+
+    Ok(())
+}
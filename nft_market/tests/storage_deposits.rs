@@ -5,7 +5,9 @@ use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
 use lemotests::prelude::*;
 use lemotests_macro::add_helpers;
 use near_contract_standards::non_fungible_token::TokenId;
+use near_contract_standards::storage_management::StorageBalance;
 use near_sdk::json_types::U128;
+use nft_market::StorageUsage;
 
 add_helpers!("./nft_schema.json", "./market_schema.json",);
 
@@ -29,7 +31,7 @@ async fn remove_deposits_works_without_stored_data_correctly() -> anyhow::Result
         .view_account(ALICE)?
         .with_label("alice_balance_before")
         .then()
-        .alice_call_market_contract_storage_withdraw()?
+        .alice_call_market_contract_storage_withdraw(None)?
         .with_gas(Tgas(10))
         .with_deposit(1)
         .then()
@@ -55,6 +57,8 @@ async fn remove_deposits_works_with_stored_one_bid_works_correctly() -> anyhow::
         .build()
         .await?;
 
+    let [_market, alice] = bchain.string_ids()?;
+
     let result = bchain
         .call_market_contract_init(NFT)?
         .with_gas(Tgas(10))
@@ -74,16 +78,23 @@ async fn remove_deposits_works_with_stored_one_bid_works_correctly() -> anyhow::
         .alice_call_market_contract_add_bid("1", None)?
         .with_gas(Tgas(10))
         .then()
-        .alice_call_market_contract_storage_withdraw()?
+        .alice_call_market_contract_storage_withdraw(None)?
         .with_gas(Tgas(10))
         .with_deposit(1)
         .with_label("storage_withdraw")
+        .then()
+        .view_market_contract_storage_usage_of(Some(&alice))?
+        .with_label("storage_usage")
         .execute()
         .await?;
 
-    let actual_withdraw: U128 = result.tx("storage_withdraw")?.json()?;
+    let storage_balance: StorageBalance = result.tx("storage_withdraw")?.json()?;
+    let storage_usage: StorageUsage = result.tx("storage_usage")?.json()?;
 
-    assert_eq!(actual_withdraw.0, 0);
+    assert!(storage_usage.used.0 > 0 && storage_usage.used.0 < minimum_storage_balance.0);
+    assert_eq!(storage_balance.total, storage_usage.used);
+    assert_eq!(storage_balance.available.0, 0);
+    assert_eq!(storage_usage.available.0, 0);
 
     Ok(())
 }
@@ -0,0 +1,185 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::{NftKind, TokenExt};
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use nft_market::Swap;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn tokens_are_swapped_with_nears_difference() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_nft_contract_nft_mint(&bob, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_add_swap("1", 1, "2", None, None, None)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(100))
+        .with_label("add_swap")
+        .then()
+        .view_market_contract_swaps_by_account(&alice, None, None)?
+        .with_label("view_swaps")
+        .execute()
+        .await?;
+
+    let swap_id = result.tx("add_swap")?.json::<u64>()?;
+    let swaps = result.tx("view_swaps")?.json::<Vec<Swap>>()?;
+    assert_eq!(swaps.len(), 1);
+    assert_eq!(swaps[0].price, U128(Near(2)));
+
+    let msg = format!("{{\"accept_swap\":{{\"swap_id\":{}}}}}", swap_id);
+    let result = result
+        .into_state()
+        .bob_call_nft_contract_nft_approve("2", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(150))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_lemon")
+        .then()
+        .view_nft_contract_nft_token("2")?
+        .with_label("view_fire_arm")
+        .then()
+        .view_market_contract_swap(swap_id)?
+        .with_label("view_swap")
+        .execute()
+        .await?;
+
+    let lemon = result.tx("view_lemon")?.json::<TokenExt>()?;
+    assert_eq!(lemon.owner_id.as_str(), bob.as_str());
+    let fire_arm = result.tx("view_fire_arm")?.json::<TokenExt>()?;
+    assert_eq!(fire_arm.owner_id.as_str(), alice.as_str());
+    assert!(result.tx("view_swap")?.json::<Option<Swap>>()?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn taker_pays_wanted_price_of_swap() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_nft_contract_nft_mint(&bob, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_add_swap("1", 1, "2", None, None, Some(U128(Near(2))))?
+        .with_gas(Tgas(100))
+        .with_label("add_swap")
+        .then()
+        .bob_call_nft_contract_nft_approve("2", &market, None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .view_account(ALICE)?
+        .with_label("alice_before")
+        .execute()
+        .await?;
+
+    let swap_id = result.tx("add_swap")?.json::<u64>()?;
+    let alice_before = result.tx("alice_before")?.balance();
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_accept_swap(swap_id, 1)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(200))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("view_lemon")
+        .then()
+        .view_nft_contract_nft_token("2")?
+        .with_label("view_fire_arm")
+        .then()
+        .view_account(ALICE)?
+        .with_label("alice_after")
+        .execute()
+        .await?;
+
+    let lemon = result.tx("view_lemon")?.json::<TokenExt>()?;
+    assert_eq!(lemon.owner_id.as_str(), bob.as_str());
+    let fire_arm = result.tx("view_fire_arm")?.json::<TokenExt>()?;
+    assert_eq!(fire_arm.owner_id.as_str(), alice.as_str());
+    let alice_after = result.tx("alice_after")?.balance();
+    assert_eq!(alice_after - alice_before, Near(2));
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use helpers::{MARKET, MARKET_PATH, SPOILED_NFT, SPOILED_NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_sdk::json_types::U128;
+
+add_helpers!(
+    "./nft_schema.json",
+    "./market_schema.json",
+    "./spoiled_nft_schema.json",
+);
+
+#[tokio::test]
+async fn failed_nft_transfer_refunds_bidder_and_removes_ask() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(SPOILED_NFT, SPOILED_NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [spoiled_nft, market, _alice, _bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_market_contract_init(&spoiled_nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_storage_minimum_balance()?
+        .with_label("minimum_deposit")
+        .execute()
+        .await?;
+
+    let required_storage_deposit = result.tx("minimum_deposit")?.json::<U128>()?.0;
+
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+    let result = result
+        .into_state()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .alice_call_spoiled_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(1)
+        .with_gas(Tgas(50))
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask_before")
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_gas(Tgas(10))
+        .with_deposit(required_storage_deposit)
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(150))
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask_after")
+        .then()
+        .view_market_contract_bids("1")?
+        .with_label("bids_after")
+        .then()
+        .view_account(BOB)?
+        .with_label("bob_balance")
+        .execute()
+        .await?;
+
+    let ask_before: Option<AskForContract> = result.tx("ask_before")?.json()?;
+    assert!(ask_before.is_some());
+    let ask_after: Option<AskForContract> = result.tx("ask_after")?.json()?;
+    assert!(ask_after.is_none());
+    let bids_after: Option<Vec<BidForContract>> = result.tx("bids_after")?.json()?;
+    assert!(bids_after.is_none());
+
+    let bob_balance = result.tx("bob_balance")?.balance();
+    assert!(
+        (Near(10) - bob_balance) <= ALMOST_ZERO,
+        "Expected bob's bid to be refunded, actual balance is {}",
+        bob_balance
+    );
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+mod helpers;
+
+use battlemon_models::market::bid::BidForContract;
+use battlemon_models::nft::{NftKind, TokenExt};
+use helpers::{MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+/// `BUY_METHOD_TOTAL_GAS` in Tgas: the order method, `nft_transfer_payout`
+/// and `on_trade` with a payment to every payout receiver, the bidder and the treasury.
+const TRADE_TGAS: u64 = 108;
+
+#[tokio::test]
+async fn bid_fails_below_gas_for_trade() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, _] = bchain.string_ids()?;
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .execute()
+        .await?;
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(TRADE_TGAS - 1))
+        .execute()
+        .await;
+
+    assert!(result.contains_error(&format!(
+        "Not enough gas for the trade, attach at least {TRADE_TGAS} Tgas"
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bid_trades_with_exact_gas_for_trade() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .execute()
+        .await?;
+
+    let result = result
+        .into_state()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(TRADE_TGAS))
+        .then()
+        .view_nft_contract_nft_token("1")?
+        .with_label("token")
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask")
+        .execute()
+        .await?;
+
+    let token: TokenExt = result.tx("token")?.json()?;
+    assert_eq!(token.owner_id.as_str(), bob.as_str());
+    let ask: Option<serde_json::Value> = result.tx("ask")?.json()?;
+    assert!(ask.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn approve_fails_below_gas_for_trade_with_bid() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, _] = bchain.string_ids()?;
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_add_bid("1", None)?
+        .with_deposit(Near(5))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_bids("1")?
+        .with_label("bids")
+        .execute()
+        .await?;
+
+    let bids: Option<Vec<BidForContract>> = result.tx("bids")?.json()?;
+    assert_eq!(bids.map(|bids| bids.len()), Some(1));
+
+    let result = result
+        .into_state()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("Not enough gas for the trade"));
+
+    Ok(())
+}
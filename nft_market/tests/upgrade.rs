@@ -0,0 +1,276 @@
+mod helpers;
+
+use battlemon_models::market::{ask::AskForContract, bid::BidForContract};
+use battlemon_models::nft::NftKind;
+use helpers::{BASELINE_MARKET_PATH, BASELINE_NFT_PATH, MARKET, MARKET_PATH, NFT, NFT_PATH};
+use lemotests::prelude::*;
+use lemotests::workspaces::operations::Function;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde_json::{json, Value};
+
+add_helpers!("./nft_schema.json", "./market_schema.json",);
+
+#[tokio::test]
+async fn upgrade_keeps_live_asks_and_bids() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let code = Base64VecU8::from(std::fs::read(MARKET_PATH)?);
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .bob_call_market_contract_add_bid("2", None)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask_before")
+        .then()
+        .view_market_contract_bids("2")?
+        .with_label("bids_before")
+        .then()
+        .view_market_contract_storage_balance_of(Some(&alice))?
+        .with_label("storage_before")
+        .then()
+        .call_market_contract_upgrade(code)?
+        .with_gas(Tgas(300))
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask_after")
+        .then()
+        .view_market_contract_bids("2")?
+        .with_label("bids_after")
+        .then()
+        .view_market_contract_storage_balance_of(Some(&alice))?
+        .with_label("storage_after")
+        .then()
+        .view_market_contract_state_version()?
+        .with_label("state_version")
+        .execute()
+        .await?;
+
+    let ask_before: AskForContract = result.tx("ask_before")?.json()?;
+    let ask_after: AskForContract = result.tx("ask_after")?.json()?;
+    assert_eq!(ask_after.account_id.as_str(), alice.as_str());
+    assert_eq!(ask_after.price, ask_before.price);
+    assert_eq!(ask_after.approval_id, ask_before.approval_id);
+
+    let bids_before: Vec<BidForContract> = result.tx("bids_before")?.json()?;
+    let bids_after: Vec<BidForContract> = result.tx("bids_after")?.json()?;
+    assert_eq!(bids_after.len(), 1);
+    assert_eq!(bids_after[0].id, bids_before[0].id);
+    assert_eq!(bids_after[0].account_id().as_str(), bob.as_str());
+
+    let storage_before: Value = result.tx("storage_before")?.json()?;
+    let storage_after: Value = result.tx("storage_after")?.json()?;
+    assert_eq!(storage_before, storage_after);
+    assert_eq!(result.tx("state_version")?.json::<u32>()?, 5);
+
+    let token_id = TokenId::from("2");
+    let result = result
+        .into_state()
+        .bob_call_market_contract_cancel_bid(&token_id, &bids_after[0].id)?
+        .with_gas(Tgas(30))
+        .then()
+        .view_market_contract_bids(token_id.as_str())?
+        .with_label("bids_cancelled")
+        .execute()
+        .await?;
+
+    let bids: Option<Vec<BidForContract>> = result.tx("bids_cancelled")?.json()?;
+    assert!(bids.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn upgrade_works_only_for_owner() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(MARKET, MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let code = Base64VecU8::from(std::fs::read(MARKET_PATH)?);
+    let result = bchain
+        .call_market_contract_init(NFT)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_upgrade(code)?
+        .with_gas(Tgas(300))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("Only the market owner can call this method"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn baseline_market_is_migrated_with_live_orders() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, BASELINE_NFT_PATH, Near(10))?
+        .with_contract(MARKET, BASELINE_MARKET_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, market, alice, bob] = bchain.string_ids()?;
+    let msg = format!("{{\"price\":\"{}\"}}", Near(5));
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_market_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::FireArm)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_storage_deposit(None)?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_approve("1", &market, Some(&msg))?
+        .with_deposit(Near(1))
+        .with_gas(Tgas(50))
+        .then()
+        .bob_call_market_contract_add_bid("2", None)?
+        .with_deposit(Near(2))
+        .with_gas(Tgas(10))
+        .then()
+        .bob_call_market_contract_add_bid("2", None)?
+        .with_deposit(Near(3))
+        .with_gas(Tgas(10))
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask_before")
+        .execute()
+        .await?;
+
+    let ask_before: AskForContract = result.tx("ask_before")?.json()?;
+
+    // The baseline contracts have no `upgrade`, the accounts deploy the new code
+    // and migrate in one transaction.
+    let state = result.into_state();
+    for (contract_id, path) in [(NFT, NFT_PATH), (MARKET, MARKET_PATH)] {
+        let code = std::fs::read(path)?;
+        let migrate = Function::new("migrate")
+            .args_json(json!({}))?
+            .gas(Tgas(100).parse());
+        let outcome = state
+            .contract(contract_id)?
+            .batch(state.worker())
+            .deploy(&code)
+            .call(migrate)
+            .transact()
+            .await?;
+        assert!(outcome.is_success());
+    }
+
+    let result = state
+        .view_market_contract_owner()?
+        .with_label("owner")
+        .then()
+        .view_market_contract_ask("1")?
+        .with_label("ask_after")
+        .then()
+        .view_market_contract_asks_by_owner(&alice, None, None)?
+        .with_label("asks_by_owner")
+        .then()
+        .view_market_contract_bids("2")?
+        .with_label("bids_after")
+        .then()
+        .view_market_contract_total_bids_by_account(&bob)?
+        .with_label("total_bids_by_account")
+        .then()
+        .view_market_contract_state_version()?
+        .with_label("state_version")
+        .then()
+        .view_nft_contract_state_version()?
+        .with_label("nft_state_version")
+        .then()
+        .view_nft_contract_nft_tokens_for_owner(&alice)?
+        .with_label("tokens")
+        .execute()
+        .await?;
+
+    assert_eq!(result.tx("owner")?.json::<String>()?, market);
+    let ask_after: AskForContract = result.tx("ask_after")?.json()?;
+    assert_eq!(ask_after.account_id.as_str(), alice.as_str());
+    assert_eq!(ask_after.price, ask_before.price);
+    assert_eq!(ask_after.approval_id, ask_before.approval_id);
+    let asks_by_owner: Vec<Value> = result.tx("asks_by_owner")?.json()?;
+    assert_eq!(asks_by_owner.len(), 1);
+
+    let bids: Vec<BidForContract> = result.tx("bids_after")?.json()?;
+    let prices: Vec<u128> = bids.iter().map(BidForContract::price).collect();
+    assert_eq!(prices, [Near(3).parse(), Near(2).parse()]);
+    assert_eq!(result.tx("total_bids_by_account")?.json::<u64>()?, 2);
+    assert_eq!(result.tx("state_version")?.json::<u32>()?, 5);
+    assert_eq!(result.tx("nft_state_version")?.json::<u32>()?, 2);
+    let tokens: Value = result.tx("tokens")?.json()?;
+    assert_eq!(tokens.as_array().map(Vec::len), Some(2));
+
+    let token_id = TokenId::from("2");
+    let result = result
+        .into_state()
+        .bob_call_market_contract_cancel_bid(&token_id, &bids[0].id)?
+        .with_gas(Tgas(30))
+        .then()
+        .view_market_contract_bids(token_id.as_str())?
+        .with_label("bids_cancelled")
+        .execute()
+        .await?;
+
+    let bids: Vec<BidForContract> = result.tx("bids_cancelled")?.json()?;
+    assert_eq!(bids.len(), 1);
+    assert_eq!(bids[0].price(), Near(2).parse());
+
+    Ok(())
+}
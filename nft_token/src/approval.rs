@@ -0,0 +1,73 @@
+use crate::{Contract, ContractExt};
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::{env, ext_contract, near_bindgen, AccountId, Promise};
+use std::collections::HashMap;
+
+/// Approved accounts, e.g. the market, learn that their approval for the token is no longer valid.
+#[ext_contract(ext_revoke_receiver)]
+pub trait NonFungibleTokenRevokeReceiver {
+    fn nft_on_revoke(&mut self, token_id: TokenId, approval_id: u64);
+}
+
+impl Contract {
+    pub(crate) fn approvals(&self, token_id: &TokenId) -> HashMap<AccountId, u64> {
+        self.tokens
+            .approvals_by_id
+            .as_ref()
+            .and_then(|by_id| by_id.get(token_id))
+            .unwrap_or_default()
+    }
+
+    /// Notify the accounts that their approvals were cleared by a transfer or revoked.
+    ///
+    /// The predecessor isn't notified, it made the change itself.
+    /// The notifications share the unused gas and their failures don't affect the call.
+    pub(crate) fn notify_revoked(&self, token_id: &TokenId, approvals: HashMap<AccountId, u64>) {
+        let predecessor_id = env::predecessor_account_id();
+        for (account_id, approval_id) in approvals {
+            if account_id != predecessor_id {
+                ext_revoke_receiver::ext(account_id).nft_on_revoke(token_id.clone(), approval_id);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl NonFungibleTokenApproval for Contract {
+    #[payable]
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        self.tokens.nft_approve(token_id, account_id, msg)
+    }
+
+    #[payable]
+    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+        let approval_id = self.approvals(&token_id).remove(&account_id);
+        self.tokens.nft_revoke(token_id.clone(), account_id.clone());
+        if let Some(approval_id) = approval_id {
+            self.notify_revoked(&token_id, HashMap::from([(account_id, approval_id)]));
+        }
+    }
+
+    #[payable]
+    fn nft_revoke_all(&mut self, token_id: TokenId) {
+        let approvals = self.approvals(&token_id);
+        self.tokens.nft_revoke_all(token_id.clone());
+        self.notify_revoked(&token_id, approvals);
+    }
+
+    fn nft_is_approved(
+        &self,
+        token_id: TokenId,
+        approved_account_id: AccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        self.tokens
+            .nft_is_approved(token_id, approved_account_id, approval_id)
+    }
+}
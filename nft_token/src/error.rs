@@ -10,6 +10,12 @@ pub enum ContractError {
     OwnerNotFound(TokenId),
     #[error("Provided instructions contain errors because: {0}")]
     InstructionError(String),
+    #[error("Failed to calculate payout: {0}")]
+    PayoutError(String),
+    #[error("Failed to set the token user: {0}")]
+    UserError(String),
+    #[error("Failed to migrate the state: {0}")]
+    MigrationError(String),
     #[error(transparent)]
     SerdeError(#[from] near_sdk::serde_json::Error),
 }
@@ -115,6 +115,7 @@ impl Contract {
 
     pub(crate) fn burn_token(&mut self, token_id: &TokenId) {
         self.model_by_id.remove(token_id);
+        self.users.remove(token_id);
         let tokens = &mut self.tokens;
         let owner_id = tokens.owner_by_id.remove(token_id).unwrap();
 
@@ -6,15 +6,17 @@ use near_contract_standards::non_fungible_token::{NonFungibleToken, Token};
 use near_sdk::borsh::{self, BorshSerialize};
 use near_sdk::collections::{LazyOption, LookupMap};
 use near_sdk::{AccountId, BorshStorageKey};
+use std::collections::HashMap;
 
 #[derive(BorshSerialize, BorshStorageKey)]
-enum StorageKey {
+pub(crate) enum StorageKey {
     Metadata,
     NonFungibleToken,
     TokenMetadata,
     Enumeration,
     Approval,
     TokenModel,
+    TokenUser,
 }
 
 impl Contract {
@@ -28,12 +30,16 @@ impl Contract {
             Some(StorageKey::Approval),
         );
         let model_by_id = LookupMap::new(StorageKey::TokenModel);
+        let users = LookupMap::new(StorageKey::TokenUser);
+        crate::upgrade::write_state_version();
 
         Self {
             tokens,
             metadata,
             model_by_id,
             last_token_id: 0,
+            royalties: HashMap::new(),
+            users,
         }
     }
 
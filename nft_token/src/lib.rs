@@ -11,20 +11,28 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LazyOption, LookupMap};
 use near_sdk::env::{self, panic_str};
 use near_sdk::json_types::U128;
-use near_sdk::{assert_one_yocto, near_bindgen, require, AccountId, PanicOnDefault, Promise};
+use near_sdk::{
+    assert_one_yocto, near_bindgen, require, AccountId, FunctionError, PanicOnDefault, Promise,
+};
+use std::collections::HashMap;
 
 use crate::consts::{DATA_IMAGE_SVG_LEMON_LOGO, IPFS_GATEWAY_BASE_URL, NFT_BACK_IMAGE};
+use crate::user::TokenUser;
 use battlemon_models::helpers_contract::{emit_log_event, weights};
 use battlemon_models::nft::{
     Back, Cap, Cloth, ColdArm, FireArm, FromTraitWeights, Lemon, ModelKind, NftEvent, NftEventKind,
     NftKind, Set, StandardKind, TokenExt, VersionKind,
 };
 
+mod approval;
 mod consts;
 mod error;
 mod helpers;
 mod internal;
 mod mint;
+mod payout;
+mod upgrade;
+mod user;
 mod xcc_handlers;
 
 #[near_bindgen]
@@ -34,6 +42,8 @@ pub struct Contract {
     metadata: LazyOption<NFTContractMetadata>,
     model_by_id: LookupMap<TokenId, ModelKind>,
     last_token_id: u128,
+    royalties: HashMap<AccountId, u32>,
+    users: LookupMap<TokenId, TokenUser>,
 }
 
 #[near_bindgen]
@@ -212,6 +222,9 @@ impl Contract {
             .expect("Couldn't collect tokens in extended format.")
     }
 
+    /// Transfer the token and clear its approvals, the approved accounts are notified.
+    ///
+    /// A token can't be transferred while it has a user, see `nft_set_user`.
     #[payable]
     pub fn nft_transfer(
         &mut self,
@@ -220,9 +233,12 @@ impl Contract {
         approval_id: Option<u64>,
         memo: Option<String>,
     ) {
+        self.check_not_used(&token_id).unwrap_or_else(|e| e.panic());
+        let approvals = self.approvals(&token_id);
         self.tokens
             .nft_transfer(receiver_id, token_id.clone(), approval_id, memo);
         self.disassemble_all(&token_id);
+        self.notify_revoked(&token_id, approvals);
     }
 
     // todo: add security checking
@@ -321,5 +337,3 @@ impl NonFungibleTokenMetadataProvider for Contract {
         self.metadata.get().expect("Metadata didn't set")
     }
 }
-
-near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
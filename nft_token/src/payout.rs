@@ -0,0 +1,104 @@
+use crate::error::{ContractError, Result};
+use crate::helpers::is_predecessor;
+use crate::{Contract, ContractExt};
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, Balance};
+use std::collections::HashMap;
+
+const ONE_HUNDRED_PERCENT_IN_BPS: u32 = 10_000;
+
+/// Accounts and amounts to pay them out after the token sale, according to NEP-199.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Set collection-wide royalties in basis points, only for the contract owner.
+    #[handle_result]
+    pub fn set_royalties(&mut self, royalties: HashMap<AccountId, u32>) -> Result<()> {
+        if !is_predecessor(&self.tokens.owner_id) {
+            return Err(ContractError::NotAuthorized(
+                "Only the contract owner can set royalties".to_string(),
+            ));
+        }
+
+        let total: u32 = royalties.values().sum();
+        if total > ONE_HUNDRED_PERCENT_IN_BPS {
+            return Err(ContractError::PayoutError(format!(
+                "Total royalties {total} exceed {ONE_HUNDRED_PERCENT_IN_BPS} basis points"
+            )));
+        }
+
+        self.royalties = royalties;
+
+        Ok(())
+    }
+
+    pub fn royalties(&self) -> &HashMap<AccountId, u32> {
+        &self.royalties
+    }
+
+    #[handle_result]
+    pub fn nft_payout(
+        &self,
+        token_id: TokenId,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Result<Payout> {
+        self.payout(&token_id, balance.0, max_len_payout)
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Result<Payout> {
+        let payout = self.payout(&token_id, balance.0, max_len_payout)?;
+        self.nft_transfer(receiver_id, token_id, approval_id, memo);
+
+        Ok(payout)
+    }
+}
+
+impl Contract {
+    /// Split `balance` between royalty holders and the token's owner.
+    ///
+    /// The owner receives everything that is left after royalties.
+    pub(crate) fn payout(
+        &self,
+        token_id: &TokenId,
+        balance: Balance,
+        max_len_payout: Option<u32>,
+    ) -> Result<Payout> {
+        let owner_id = self.owner(token_id)?;
+        let payout_len = self.royalties.len() as u32 + 1;
+        if matches!(max_len_payout, Some(max_len_payout) if payout_len > max_len_payout) {
+            return Err(ContractError::PayoutError(format!(
+                "Payout length {payout_len} exceeds the provided maximum"
+            )));
+        }
+
+        let mut payout = HashMap::new();
+        let mut royalties_total = 0;
+        for (account_id, bps) in self.royalties.iter() {
+            let amount = balance * *bps as u128 / ONE_HUNDRED_PERCENT_IN_BPS as u128;
+            royalties_total += amount;
+            payout.insert(account_id.to_owned(), U128(amount));
+        }
+
+        payout.entry(owner_id).or_insert(U128(0)).0 += balance - royalties_total;
+
+        Ok(Payout { payout })
+    }
+}
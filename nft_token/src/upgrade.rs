@@ -0,0 +1,145 @@
+use crate::consts::MIGRATE_GAS;
+use crate::error::{ContractError, Result};
+use crate::helpers::is_predecessor;
+use crate::internal::StorageKey;
+use crate::{Contract, ContractExt};
+use battlemon_models::nft::ModelKind;
+use near_contract_standards::non_fungible_token::metadata::NFTContractMetadata;
+use near_contract_standards::non_fungible_token::{NonFungibleToken, TokenId};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near_bindgen, AccountId, FunctionError, Promise};
+use std::collections::HashMap;
+
+/// The version is stored apart from the state, so it can be read before the state layout is known.
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+/// Version of the current `Contract` layout.
+const STATE_VERSION: u32 = 2;
+
+/// The `Contract` layout of the deployments made before versioning, without royalties.
+#[derive(BorshDeserialize)]
+struct ContractV0 {
+    tokens: NonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    model_by_id: LookupMap<TokenId, ModelKind>,
+    last_token_id: u128,
+}
+
+/// The `Contract` layout before tokens got users.
+#[derive(BorshDeserialize)]
+struct ContractV1 {
+    tokens: NonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    model_by_id: LookupMap<TokenId, ModelKind>,
+    last_token_id: u128,
+    royalties: HashMap<AccountId, u32>,
+}
+
+/// The token contract state in every layout it has been deployed with.
+///
+/// When the layout of `Contract` changes, freeze the old layout as `ContractV2`,
+/// read it into its own variant and convert it in `into_current`.
+enum VersionedContract {
+    V0(ContractV0),
+    V1(ContractV1),
+    V2(Contract),
+}
+
+impl VersionedContract {
+    /// Deployments made before versioning have no stored version and use the `V0` layout.
+    fn read() -> Result<Self> {
+        let state = match stored_state_version().unwrap_or(0) {
+            0 => env::state_read().map(Self::V0),
+            1 => env::state_read().map(Self::V1),
+            2 => env::state_read().map(Self::V2),
+            version => {
+                return Err(ContractError::MigrationError(format!(
+                    "Unknown state version {version}"
+                )))
+            }
+        };
+
+        state.ok_or_else(|| {
+            ContractError::MigrationError("The contract isn't initialized".to_string())
+        })
+    }
+
+    fn owner_id(&self) -> &AccountId {
+        match self {
+            Self::V0(contract) => &contract.tokens.owner_id,
+            Self::V1(contract) => &contract.tokens.owner_id,
+            Self::V2(contract) => &contract.tokens.owner_id,
+        }
+    }
+
+    fn into_current(self) -> Contract {
+        match self {
+            Self::V0(contract) => Contract {
+                tokens: contract.tokens,
+                metadata: contract.metadata,
+                model_by_id: contract.model_by_id,
+                last_token_id: contract.last_token_id,
+                royalties: HashMap::new(),
+                users: LookupMap::new(StorageKey::TokenUser),
+            },
+            Self::V1(contract) => Contract {
+                tokens: contract.tokens,
+                metadata: contract.metadata,
+                model_by_id: contract.model_by_id,
+                last_token_id: contract.last_token_id,
+                royalties: contract.royalties,
+                users: LookupMap::new(StorageKey::TokenUser),
+            },
+            Self::V2(contract) => contract,
+        }
+    }
+}
+
+fn stored_state_version() -> Option<u32> {
+    env::storage_read(STATE_VERSION_KEY).map(|bytes| {
+        u32::try_from_slice(&bytes).unwrap_or_else(|_| env::panic_str("Invalid state version"))
+    })
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Convert the stored state into the current layout after new code is deployed.
+    ///
+    /// Only the contract owner or the contract itself, via `upgrade`, can migrate.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read().unwrap_or_else(|e| e.panic());
+        if !is_predecessor(state.owner_id()) && !is_predecessor(&env::current_account_id()) {
+            ContractError::NotAuthorized(
+                "Only the contract owner can migrate the state".to_string(),
+            )
+            .panic();
+        }
+
+        write_state_version();
+        state.into_current()
+    }
+
+    /// Deploy the new code and migrate the state in one batch, a failed migration reverts the deploy.
+    #[handle_result]
+    pub fn upgrade(&self, code: Base64VecU8) -> Result<Promise> {
+        if !is_predecessor(&self.tokens.owner_id) {
+            return Err(ContractError::NotAuthorized(
+                "Only the contract owner can upgrade the contract".to_string(),
+            ));
+        }
+
+        Ok(Promise::new(env::current_account_id())
+            .deploy_contract(code.into())
+            .function_call("migrate".to_string(), Vec::new(), 0, MIGRATE_GAS))
+    }
+
+    pub fn state_version(&self) -> u32 {
+        stored_state_version().unwrap_or(0)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::consts::MAX_USER_DURATION;
+use crate::error::{ContractError, Result};
+use crate::helpers::is_predecessor;
+use crate::{Contract, ContractExt};
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{assert_one_yocto, env, near_bindgen, AccountId};
+
+/// Account which may use the token in the game until `expires_at`, while the owner keeps the token.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct TokenUser {
+    pub user_id: AccountId,
+    /// Timestamp in nanoseconds.
+    pub expires_at: u64,
+}
+
+impl Contract {
+    /// The token's user whose rights haven't expired yet.
+    pub(crate) fn active_user(&self, token_id: &TokenId) -> Option<TokenUser> {
+        self.users
+            .get(token_id)
+            .filter(|user| user.expires_at > env::block_timestamp())
+    }
+
+    /// The token can't change its owner while it has a user.
+    pub(crate) fn check_not_used(&self, token_id: &TokenId) -> Result<()> {
+        match self.active_user(token_id) {
+            Some(user) => Err(ContractError::UserError(format!(
+                "The token {token_id} is used by {} until {}",
+                user.user_id, user.expires_at
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Give the user rights for the token until `expires_at`, e.g. when the token is rented.
+    ///
+    /// The owner or an approved account, with the current `approval_id` if it's provided,
+    /// can set the user while the token has no user, for at most `MAX_USER_DURATION`.
+    #[payable]
+    #[handle_result]
+    pub fn nft_set_user(
+        &mut self,
+        token_id: TokenId,
+        user_id: AccountId,
+        expires_at: u64,
+        approval_id: Option<u64>,
+    ) -> Result<()> {
+        assert_one_yocto();
+        let owner_id = self.owner(&token_id)?;
+        let predecessor_id = env::predecessor_account_id();
+        if !is_predecessor(&owner_id)
+            && !self
+                .tokens
+                .nft_is_approved(token_id.clone(), predecessor_id, approval_id)
+        {
+            return Err(ContractError::NotAuthorized(
+                "Only the owner or an approved account can set the token user".to_string(),
+            ));
+        }
+        self.check_not_used(&token_id)?;
+        let now = env::block_timestamp();
+        if expires_at <= now {
+            return Err(ContractError::UserError(
+                "The expiration time is already in the past".to_string(),
+            ));
+        }
+        if expires_at - now > MAX_USER_DURATION {
+            return Err(ContractError::UserError(format!(
+                "The token can't have a user for more than {MAX_USER_DURATION} nanoseconds"
+            )));
+        }
+
+        self.users.insert(
+            &token_id,
+            &TokenUser {
+                user_id,
+                expires_at,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// The account which may use the token, `None` if the token has no user or the rights expired.
+    pub fn nft_user_of(&self, token_id: TokenId) -> Option<AccountId> {
+        self.active_user(&token_id).map(|user| user.user_id)
+    }
+
+    /// When the rights of the token's user expire, in nanoseconds.
+    pub fn nft_user_expires(&self, token_id: TokenId) -> Option<u64> {
+        self.active_user(&token_id).map(|user| user.expires_at)
+    }
+}
@@ -63,6 +63,7 @@ impl FungibleTokenReceiver for Contract {
                     "`sender_id` doesn't equal to token's owner.".to_string(),
                 ));
             }
+            self.check_not_used(id)?;
         }
 
         let random = battlemon_models::helpers_contract::get_random_arr_range(0, 100);
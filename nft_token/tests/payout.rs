@@ -0,0 +1,72 @@
+use battlemon_models::nft::NftKind;
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+use near_contract_standards::non_fungible_token::TokenId;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use std::collections::HashMap;
+
+const NFT_PATH: &str = "../target/wasm32-unknown-unknown/release/nft_token.wasm";
+const NFT: &str = "nft_contract";
+
+add_helpers!("./nft_schema.json");
+
+#[tokio::test]
+async fn nft_payout_splits_balance_between_royalties_and_owner() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice, bob] = bchain.string_ids()?;
+    let royalties = HashMap::from([(bob.parse::<AccountId>()?, 1_000)]);
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .call_nft_contract_set_royalties(royalties)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .view_nft_contract_nft_payout("1", U128(10_000), None)?
+        .with_label("payout")
+        .execute()
+        .await?;
+
+    let payout: serde_json::Value = result.tx("payout")?.json()?;
+    assert_eq!(payout["payout"][&bob], "1000");
+    assert_eq!(payout["payout"][&alice], "9000");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn set_royalties_works_only_for_owner() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice] = bchain.string_ids()?;
+    let royalties = HashMap::from([(alice.parse::<AccountId>()?, 1_000)]);
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_set_royalties(royalties)?
+        .with_gas(Tgas(10))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("Only the contract owner can set royalties"));
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use battlemon_models::nft::{NftKind, TokenExt};
+use lemotests::prelude::*;
+use lemotests::workspaces::operations::Function;
+use lemotests_macro::add_helpers;
+use near_sdk::json_types::Base64VecU8;
+use serde_json::{json, Value};
+
+const NFT_PATH: &str = "../target/wasm32-unknown-unknown/release/nft_token.wasm";
+/// The contract deployed before state versioning, built by `scripts/build_baseline_wasm.sh`.
+const BASELINE_NFT_PATH: &str = "../res/baseline/nft_token.wasm";
+const NFT: &str = "nft_contract";
+
+add_helpers!("./nft_schema.json");
+
+#[tokio::test]
+async fn upgrade_keeps_minted_tokens() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice] = bchain.string_ids()?;
+    let code = Base64VecU8::from(std::fs::read(NFT_PATH)?);
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint_full(&alice)?
+        .with_gas(Tgas(100))
+        .with_deposit(Near(1))
+        .then()
+        .view_nft_contract_nft_tokens_for_owner(&alice)?
+        .with_label("tokens_before")
+        .then()
+        .call_nft_contract_upgrade(code)?
+        .with_gas(Tgas(300))
+        .then()
+        .view_nft_contract_nft_tokens_for_owner(&alice)?
+        .with_label("tokens_after")
+        .then()
+        .view_nft_contract_state_version()?
+        .with_label("state_version")
+        .execute()
+        .await?;
+
+    let tokens_before: Value = result.tx("tokens_before")?.json()?;
+    let tokens_after: Value = result.tx("tokens_after")?.json()?;
+    assert_eq!(tokens_before.as_array().map(Vec::len), Some(6));
+    assert_eq!(tokens_before, tokens_after);
+    assert_eq!(result.tx("state_version")?.json::<u32>()?, 2);
+
+    let result = result
+        .into_state()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .with_label("lemon")
+        .execute()
+        .await?;
+
+    let lemon: TokenExt = result.tx("lemon")?.json()?;
+    assert_eq!(lemon.token_id, "7");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn upgrade_works_only_for_owner() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, _alice] = bchain.string_ids()?;
+    let code = Base64VecU8::from(std::fs::read(NFT_PATH)?);
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_upgrade(code)?
+        .with_gas(Tgas(300))
+        .execute()
+        .await;
+
+    assert!(result.contains_error("Only the contract owner can upgrade the contract"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn baseline_contract_is_migrated_with_minted_tokens() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, BASELINE_NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint_full(&alice)?
+        .with_gas(Tgas(100))
+        .with_deposit(Near(1))
+        .then()
+        .view_nft_contract_nft_tokens_for_owner(&alice)?
+        .with_label("tokens_before")
+        .execute()
+        .await?;
+
+    let tokens_before: Value = result.tx("tokens_before")?.json()?;
+
+    // The baseline contract has no `upgrade`, the account deploys the new code
+    // and migrates in one transaction.
+    let state = result.into_state();
+    let code = std::fs::read(NFT_PATH)?;
+    let migrate = Function::new("migrate")
+        .args_json(json!({}))?
+        .gas(Tgas(100).parse());
+    let outcome = state
+        .contract(NFT)?
+        .batch(state.worker())
+        .deploy(&code)
+        .call(migrate)
+        .transact()
+        .await?;
+    assert!(outcome.is_success());
+
+    let result = state
+        .view_nft_contract_nft_tokens_for_owner(&alice)?
+        .with_label("tokens_after")
+        .then()
+        .view_nft_contract_state_version()?
+        .with_label("state_version")
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .with_label("lemon")
+        .execute()
+        .await?;
+
+    let tokens_after: Value = result.tx("tokens_after")?.json()?;
+    assert_eq!(tokens_before.as_array().map(Vec::len), Some(6));
+    assert_eq!(tokens_before, tokens_after);
+    assert_eq!(result.tx("state_version")?.json::<u32>()?, 2);
+    let lemon: TokenExt = result.tx("lemon")?.json()?;
+    assert_eq!(lemon.token_id, "7");
+
+    Ok(())
+}
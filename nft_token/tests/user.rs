@@ -0,0 +1,128 @@
+use battlemon_models::nft::NftKind;
+use lemotests::prelude::*;
+use lemotests_macro::add_helpers;
+
+const NFT_PATH: &str = "../target/wasm32-unknown-unknown/release/nft_token.wasm";
+const NFT: &str = "nft_contract";
+/// One year in nanoseconds, the longest time the token can have a user.
+const MAX_USER_DURATION: u64 = 365 * 24 * 60 * 60 * 1_000_000_000;
+
+/// A day after now, in nanoseconds.
+fn expires_at() -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap();
+    now.as_nanos() as u64 + 24 * 60 * 60 * 1_000_000_000
+}
+
+add_helpers!("./nft_schema.json");
+
+#[tokio::test]
+async fn token_with_user_can_not_be_transferred() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice, bob] = bchain.string_ids()?;
+    let expires_at = expires_at();
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .alice_call_nft_contract_nft_set_user("1", &bob, expires_at, None)?
+        .with_gas(Tgas(10))
+        .with_deposit(1)
+        .then()
+        .view_nft_contract_nft_user_of("1")?
+        .with_label("user")
+        .then()
+        .view_nft_contract_nft_user_expires("1")?
+        .with_label("expires_at")
+        .execute()
+        .await?;
+
+    let user: Option<String> = result.tx("user")?.json()?;
+    assert_eq!(user.as_deref(), Some(bob.as_str()));
+    let user_expires: Option<u64> = result.tx("expires_at")?.json()?;
+    assert_eq!(user_expires, Some(expires_at));
+
+    let result = result
+        .into_state()
+        .alice_call_nft_contract_nft_transfer(&bob, "1", None, None)?
+        .with_gas(Tgas(10))
+        .with_deposit(1)
+        .execute()
+        .await;
+
+    assert!(result.contains_error("is used by"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn user_is_set_only_by_owner_or_approved_account() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .bob_call_nft_contract_nft_set_user("1", &bob, expires_at(), None)?
+        .with_gas(Tgas(10))
+        .with_deposit(1)
+        .execute()
+        .await;
+
+    assert!(result.contains_error("Only the owner or an approved account can set the token user"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn user_is_set_for_at_most_max_duration() -> anyhow::Result<()> {
+    let bchain = StateBuilder::sandbox()
+        .with_contract(NFT, NFT_PATH, Near(10))?
+        .with_alice(Near(10))?
+        .with_bob(Near(10))?
+        .build()
+        .await?;
+
+    let [nft, alice, bob] = bchain.string_ids()?;
+
+    let result = bchain
+        .call_nft_contract_init(&nft)?
+        .with_gas(Tgas(10))
+        .then()
+        .alice_call_nft_contract_nft_mint(&alice, NftKind::Lemon)?
+        .with_gas(Tgas(10))
+        .with_deposit(Near(1))
+        .then()
+        .alice_call_nft_contract_nft_set_user("1", &bob, expires_at() + MAX_USER_DURATION, None)?
+        .with_gas(Tgas(10))
+        .with_deposit(1)
+        .execute()
+        .await;
+
+    assert!(result.contains_error("The token can't have a user for more than"));
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+#!/bin/bash
+
+# Builds the NFT and market contracts deployed before state versioning into ./res/baseline,
+# the upgrade tests deploy them and migrate their state.
+set -e
+BASELINE_COMMIT=34b9205
+OUT_DIR=./res/baseline
+WORKTREE=$(mktemp -d)
+
+git worktree add --detach "$WORKTREE" $BASELINE_COMMIT
+(cd "$WORKTREE" && cargo build --release --target wasm32-unknown-unknown -p nft_token -p nft_market)
+
+mkdir -p $OUT_DIR
+cp "$WORKTREE"/target/wasm32-unknown-unknown/release/nft_token.wasm $OUT_DIR
+cp "$WORKTREE"/target/wasm32-unknown-unknown/release/nft_market.wasm $OUT_DIR
+git worktree remove --force "$WORKTREE"
@@ -1,7 +1,19 @@
 use battlemon_models::nft::TokenExt;
 use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, near_bindgen};
+use near_sdk::json_types::U128;
+use near_sdk::{env, ext_contract, near_bindgen, AccountId, Promise};
+
+#[ext_contract(ext_approval_receiver)]
+trait NonFungibleTokenApprovalReceiver {
+    fn nft_on_approve(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        approval_id: u64,
+        msg: String,
+    );
+}
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, Default)]
@@ -13,4 +25,47 @@ impl Contract {
     pub fn nft_token(&self, token_id: TokenId) -> Option<TokenExt> {
         env::panic_str("this is spoiled `nft_token` method.");
     }
+
+    /// Notifies `account_id` about the approval without storing it,
+    /// so any following `nft_transfer` fails.
+    #[payable]
+    pub fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Promise {
+        ext_approval_receiver::ext(account_id).nft_on_approve(
+            token_id,
+            env::predecessor_account_id(),
+            1,
+            msg.unwrap_or_default(),
+        )
+    }
+
+    #[payable]
+    #[allow(unused_variables)]
+    pub fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        env::panic_str("this is spoiled `nft_transfer` method.");
+    }
+
+    #[payable]
+    #[allow(unused_variables)]
+    pub fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) {
+        env::panic_str("this is spoiled `nft_transfer_payout` method.");
+    }
 }